@@ -0,0 +1,823 @@
+use std::{collections::HashSet, fmt, ops::Range, str::FromStr, sync::OnceLock};
+
+use crate::{
+    intervals::{AnyInterval, Diatonic, ImperfectInterval, Interval, PerfectInterval},
+    notes::{octave, Accidentals, Note, NoteName, Notes, Pitch},
+    scales::{Qualities, Scale, Tensions},
+};
+
+use ImperfectInterval::*;
+use Interval::*;
+use PerfectInterval::*;
+
+impl Qualities {
+    /// The intervals of the triad with this quality.
+    pub fn intervals(&self) -> Vec<Interval> {
+        match *self {
+            Qualities::Major => vec![Perfect(Unison), Interval::Major(Third), Perfect(Fifth)],
+            Qualities::Minor => vec![Perfect(Unison), Interval::Minor(Third), Perfect(Fifth)],
+            Qualities::Diminshed => vec![
+                Perfect(Unison),
+                Interval::Minor(Third),
+                Interval::Diminshed(AnyInterval::Perfect(Fifth)),
+            ],
+            Qualities::Augmented => vec![
+                Perfect(Unison),
+                Interval::Major(Third),
+                Interval::Augmented(AnyInterval::Perfect(Fifth)),
+            ],
+            Qualities::Sus4 => vec![Perfect(Unison), Perfect(Fourth), Perfect(Fifth)],
+            Qualities::Sus2 => vec![Perfect(Unison), Interval::Major(Second), Perfect(Fifth)],
+        }
+    }
+}
+
+impl Tensions {
+    /// The interval this tension adds to a chord of the given quality. A plain seventh is
+    /// diminished on a diminished chord and minor otherwise.
+    pub fn interval(&self, quality: Qualities) -> Interval {
+        use Tensions::*;
+        match *self {
+            Sixth => Interval::Major(ImperfectInterval::Sixth),
+            Tensions::Seventh => match quality {
+                Qualities::Diminshed => {
+                    Interval::Diminshed(AnyInterval::Imperfect(ImperfectInterval::Seventh))
+                }
+                _ => Interval::Minor(ImperfectInterval::Seventh),
+            },
+            MajorSeventh => Interval::Major(ImperfectInterval::Seventh),
+            Tensions::Ninth => Interval::Major(ImperfectInterval::Ninth),
+            Eleventh => Perfect(PerfectInterval::Eleventh),
+            Tensions::Thirteenth => Interval::Major(ImperfectInterval::Thirteenth),
+        }
+    }
+}
+
+/// A raised or lowered chord tone. An alteration replaces the unaltered tone of the same
+/// degree, so a chord can carry both a flat and a sharp ninth.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Alterations {
+    FlatFifth,
+    SharpFifth,
+    FlatNinth,
+    SharpNinth,
+    SharpEleventh,
+    FlatThirteenth,
+}
+
+impl Alterations {
+    pub fn interval(&self) -> Interval {
+        use Alterations::*;
+        match *self {
+            FlatFifth => Diminshed(AnyInterval::Perfect(Fifth)),
+            SharpFifth => Augmented(AnyInterval::Perfect(Fifth)),
+            FlatNinth => Minor(Ninth),
+            SharpNinth => Augmented(AnyInterval::Imperfect(Ninth)),
+            SharpEleventh => Augmented(AnyInterval::Perfect(Eleventh)),
+            FlatThirteenth => Minor(Thirteenth),
+        }
+    }
+}
+
+/// A chord tone left out of the chord, as in `C7omit3`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Omissions {
+    Third,
+    Fifth,
+}
+
+impl Omissions {
+    pub fn diatonic_steps(&self) -> u32 {
+        match *self {
+            Omissions::Third => 2,
+            Omissions::Fifth => 4,
+        }
+    }
+}
+
+/// The alterations written as `alt` in a chord symbol.
+pub const ALTERED: [Alterations; 4] = [
+    Alterations::FlatFifth,
+    Alterations::SharpFifth,
+    Alterations::FlatNinth,
+    Alterations::SharpNinth,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub root: Note,
+    pub quality: Qualities,
+    pub tensions: Vec<Tensions>,
+    pub alterations: Vec<Alterations>,
+    pub omissions: Vec<Omissions>,
+    pub bass: Option<Pitch>,
+}
+
+impl Chord {
+    pub fn new(root: Note, quality: Qualities) -> Chord {
+        Chord {
+            root,
+            quality,
+            tensions: vec![],
+            alterations: vec![],
+            omissions: vec![],
+            bass: None,
+        }
+    }
+
+    /// The intervals of all chord tones above the root, ordered by size. The bass of a slash
+    /// chord is not included.
+    pub fn intervals(&self) -> Vec<Interval> {
+        let mut intervals = self.quality.intervals();
+        intervals.extend(self.tensions.iter().map(|t| t.interval(self.quality)));
+
+        intervals.retain(|interval| {
+            !self
+                .omissions
+                .iter()
+                .any(|o| o.diatonic_steps() == interval.diatonic_steps())
+                && !self
+                    .alterations
+                    .iter()
+                    .any(|a| a.interval().diatonic_steps() == interval.diatonic_steps())
+        });
+        intervals.extend(self.alterations.iter().map(|a| a.interval()));
+
+        intervals.sort_by_key(|a| a.size());
+        intervals.dedup();
+        intervals
+    }
+
+    fn has(&self, tension: Tensions) -> bool {
+        self.tensions.contains(&tension)
+    }
+
+    fn is_altered(&self) -> bool {
+        let mut alterations = self.alterations.clone();
+        alterations.sort();
+        alterations.dedup();
+        alterations == ALTERED
+    }
+
+    /// The chord symbol without root and bass, e.g. `m7♭5`.
+    pub(crate) fn symbol(&self) -> String {
+        use Tensions::*;
+
+        let (quality, sus) = match self.quality {
+            Qualities::Major => ("", ""),
+            Qualities::Minor => ("m", ""),
+            Qualities::Diminshed => ("dim", ""),
+            Qualities::Augmented => ("aug", ""),
+            Qualities::Sus4 => ("", "sus4"),
+            Qualities::Sus2 => ("", "sus2"),
+        };
+        let mut symbol = quality.to_string();
+
+        let power = self.quality == Qualities::Major
+            && self.omissions == [Omissions::Third]
+            && self.tensions.is_empty()
+            && self.alterations.is_empty();
+        if power {
+            return "5".to_string();
+        }
+
+        let seventh = self.has(Tensions::Seventh) || self.has(MajorSeventh);
+        let mut adds = vec![];
+        if seventh {
+            let extension = if self.has(Tensions::Thirteenth) {
+                13
+            } else if self.has(Eleventh) {
+                11
+            } else if self.has(Tensions::Ninth) {
+                9
+            } else {
+                7
+            };
+
+            if !self.has(MajorSeventh) {
+                symbol += &extension.to_string();
+            } else if quality.is_empty() {
+                symbol += &format!("maj{}", extension);
+            } else {
+                symbol += &format!("(maj{})", extension);
+            }
+
+            if self.has(Tensions::Sixth) {
+                adds.push(6);
+            }
+        } else {
+            if self.has(Tensions::Sixth) {
+                symbol += "6";
+                if self.has(Tensions::Ninth) {
+                    symbol += "/9";
+                }
+            } else if self.has(Tensions::Ninth) {
+                adds.push(9);
+            }
+            if self.has(Eleventh) {
+                adds.push(11);
+            }
+            if self.has(Tensions::Thirteenth) {
+                adds.push(13);
+            }
+        }
+
+        symbol += sus;
+        for add in adds {
+            symbol += &format!("add{}", add);
+        }
+        for omission in &self.omissions {
+            symbol += match omission {
+                Omissions::Third => "omit3",
+                Omissions::Fifth => "omit5",
+            };
+        }
+
+        if seventh && self.is_altered() {
+            symbol += "alt";
+        } else if !self.alterations.is_empty() {
+            let mut alterations = self.alterations.clone();
+            alterations.sort();
+            alterations.dedup();
+            let alterations: String = alterations.iter().map(|a| a.to_string()).collect();
+            if seventh {
+                symbol += &alterations;
+            } else {
+                symbol += &format!("({})", alterations);
+            }
+        }
+
+        symbol
+    }
+}
+
+/// Spells the chord from its root upwards. The bass of a slash chord is placed below the
+/// root and listed first, unless the root is too low for that, in which case it is placed in
+/// octave 0.
+pub fn spell(chord: &Chord) -> Notes {
+    let mut notes = vec![];
+
+    if let Some(bass) = chord.bass {
+        let mut bass = Note {
+            pitch: bass,
+            octave: chord.root.octave,
+        };
+        if bass.position() >= chord.root.position() {
+            bass.octave = bass.octave.saturating_sub(1);
+        }
+        notes.push(bass);
+    }
+
+    for interval in chord.intervals() {
+        notes.push(chord.root.leap(interval));
+    }
+
+    Notes(notes)
+}
+
+const QUALITIES: [Qualities; 6] = [
+    Qualities::Major,
+    Qualities::Minor,
+    Qualities::Diminshed,
+    Qualities::Augmented,
+    Qualities::Sus4,
+    Qualities::Sus2,
+];
+
+const ALTERATIONS: [Alterations; 6] = [
+    Alterations::FlatFifth,
+    Alterations::SharpFifth,
+    Alterations::FlatNinth,
+    Alterations::SharpNinth,
+    Alterations::SharpEleventh,
+    Alterations::FlatThirteenth,
+];
+
+/// Every chord shape recognition tries, rooted on C4, with its intervals. Built once on
+/// first use.
+fn shapes() -> &'static [(Chord, Vec<Interval>)] {
+    static SHAPES: OnceLock<Vec<(Chord, Vec<Interval>)>> = OnceLock::new();
+    SHAPES.get_or_init(|| {
+        build_shapes()
+            .into_iter()
+            .map(|shape| {
+                let intervals = shape.intervals();
+                (shape, intervals)
+            })
+            .collect()
+    })
+}
+
+/// Builds the shapes for `shapes`. Shapes with alterations or omissions that do not change
+/// the chord are left out.
+fn build_shapes() -> Vec<Chord> {
+    use Tensions::*;
+
+    let mut tension_sets = vec![];
+    for seventh in [None, Some(Tensions::Seventh), Some(MajorSeventh)] {
+        for mask in 0..16 {
+            let mut tensions: Vec<Tensions> = seventh.into_iter().collect();
+            for (bit, tension) in [
+                Tensions::Sixth,
+                Tensions::Ninth,
+                Eleventh,
+                Tensions::Thirteenth,
+            ]
+            .iter()
+            .enumerate()
+            {
+                if mask & (1 << bit) != 0 {
+                    tensions.push(*tension);
+                }
+            }
+            // Without a seventh a thirteenth is written as a sixth, with one the other way
+            // around.
+            let sixth = tensions.contains(&Tensions::Sixth);
+            let thirteenth = tensions.contains(&Tensions::Thirteenth);
+            if (sixth && (seventh.is_some() || thirteenth)) || (thirteenth && seventh.is_none()) {
+                continue;
+            }
+            tension_sets.push(tensions);
+        }
+    }
+
+    let mut alteration_sets = vec![vec![], ALTERED.to_vec()];
+    for (i, first) in ALTERATIONS.iter().enumerate() {
+        alteration_sets.push(vec![*first]);
+        for second in &ALTERATIONS[i + 1..] {
+            alteration_sets.push(vec![*first, *second]);
+        }
+    }
+
+    let omission_sets = [vec![], vec![Omissions::Third], vec![Omissions::Fifth]];
+
+    let mut shapes = vec![];
+    for quality in QUALITIES {
+        for tensions in &tension_sets {
+            // A diminished sixth chord is written as a diminished seventh.
+            if quality == Qualities::Diminshed && tensions.contains(&Tensions::Sixth) {
+                continue;
+            }
+
+            let mut chord = Chord::new(octave(crate::c!(), 4), quality);
+            chord.tensions = tensions.clone();
+            let unaltered = chord.intervals();
+
+            for alterations in &alteration_sets {
+                if alterations
+                    .iter()
+                    .any(|a| unaltered.contains(&a.interval()))
+                {
+                    continue;
+                }
+
+                for omissions in &omission_sets {
+                    let useful = omissions.iter().all(|o| {
+                        unaltered
+                            .iter()
+                            .any(|i| i.diatonic_steps() == o.diatonic_steps())
+                            && !alterations
+                                .iter()
+                                .any(|a| a.interval().diatonic_steps() == o.diatonic_steps())
+                    });
+                    if !useful {
+                        continue;
+                    }
+
+                    let mut chord = chord.clone();
+                    chord.alterations = alterations.clone();
+                    chord.omissions = omissions.clone();
+                    shapes.push(chord);
+                }
+            }
+        }
+    }
+
+    shapes
+}
+
+impl Chord {
+    /// How unusual a chord symbol is, used to rank recognised chords.
+    fn complexity(&self) -> usize {
+        let quality = match self.quality {
+            Qualities::Major | Qualities::Minor => 0,
+            _ => 1,
+        };
+        let omissions: usize = self
+            .omissions
+            .iter()
+            .map(|o| match o {
+                Omissions::Third => 3,
+                Omissions::Fifth => 1,
+            })
+            .sum();
+        let bass = if self.bass.is_some() { 2 } else { 0 };
+
+        // A half-diminished chord is spelled as m7♭5 but is as common as any seventh chord.
+        let half_diminished = self.quality == Qualities::Minor
+            && self.tensions == [Tensions::Seventh]
+            && self.alterations == [Alterations::FlatFifth];
+        let alterations = if half_diminished {
+            1
+        } else {
+            3 * self.alterations.len()
+        };
+
+        quality + self.tensions.len() + alterations + omissions + bass
+    }
+}
+
+/// Recognises the chords formed by `tones` that are rooted on one of `roots`.
+fn recognize_from(tones: &[Note], roots: &[Note], bass: Option<Pitch>) -> Vec<Chord> {
+    let spelled: HashSet<Pitch> = tones.iter().map(|n| n.pitch).collect();
+    let classes: HashSet<i32> = spelled.iter().map(Pitch::pitch_class).collect();
+
+    let shapes: Vec<&(Chord, Vec<Interval>)> = shapes()
+        .iter()
+        .filter(|(_, intervals)| intervals.len() == classes.len())
+        .collect();
+
+    let mut candidates = vec![];
+    for root in roots {
+        for (shape, intervals) in &shapes {
+            let tones: Vec<Pitch> = intervals.iter().map(|&i| root.pitch.leap(i)).collect();
+            let tone_classes: HashSet<i32> = tones.iter().map(Pitch::pitch_class).collect();
+            if tone_classes != classes {
+                continue;
+            }
+
+            let mut chord = Chord {
+                root: *root,
+                ..shape.clone()
+            };
+            chord.bass = bass.filter(|b| b.pitch_class() != root.pitch.pitch_class());
+
+            let respelled = tones.iter().filter(|t| !spelled.contains(t)).count();
+            candidates.push((chord.complexity() + respelled, chord));
+        }
+    }
+
+    candidates.sort_by_key(|(complexity, chord)| (*complexity, chord.bass.is_some()));
+    candidates.into_iter().map(|(_, chord)| chord).collect()
+}
+
+/// Names the chord formed by `notes`, with every interpretation on every root ranked most
+/// likely first. The lowest note is taken as the bass, so inversions come out as slash
+/// chords. Chord tones are matched enharmonically, but interpretations that keep the given
+/// spelling are preferred.
+pub fn recognize(notes: &Notes) -> Vec<Chord> {
+    let mut roots = notes.clone();
+    roots.sort_by_pitch();
+    let bass = roots.lowest().map(|n| n.pitch);
+
+    let mut seen = HashSet::new();
+    roots.0.retain(|n| seen.insert(n.pitch));
+    recognize_from(&roots.0, &roots.0, bass)
+}
+
+/// Like `recognize`, for a set of pitches without a bass note.
+pub fn recognize_pitches(pitches: &HashSet<Pitch>) -> Vec<Chord> {
+    let mut roots: Vec<Note> = pitches.iter().map(|&p| octave(p, 4)).collect();
+    roots.sort();
+    recognize_from(&roots, &roots, None)
+}
+
+/// How many thirds to stack on each degree when harmonizing a scale.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Extensions {
+    Triad,
+    Seventh,
+    Ninth,
+}
+
+impl Extensions {
+    fn tones(&self) -> usize {
+        match *self {
+            Extensions::Triad => 3,
+            Extensions::Seventh => 4,
+            Extensions::Ninth => 5,
+        }
+    }
+}
+
+/// Builds the chord on the `degree`-th note of `scale`, counting from 1, by stacking every
+/// other scale note, e.g. Dm7 on the second degree of C major. The quality follows from the
+/// scale, so the same degree gives Dm7♭5 in C minor. Returns `None` for degree 0, or if the
+/// stacked notes do not form a chord that can be named.
+pub fn diatonic_chord<S: Scale>(scale: S, degree: usize, extension: Extensions) -> Option<Chord> {
+    let tones: Vec<Note> = (0..extension.tones())
+        .map(|i| scale.degree(degree + 2 * i))
+        .collect::<Option<_>>()?;
+    recognize_from(&tones, &tones[..1], None).into_iter().next()
+}
+
+/// Builds the chord on every degree of `scale`, see `diatonic_chord`. C major gives Cmaj7,
+/// Dm7, Em7, Fmaj7, G7, Am7 and Bm7♭5 for `Extensions::Seventh`.
+pub fn harmonize<S: Scale>(scale: S, extension: Extensions) -> Vec<Option<Chord>> {
+    (1..=scale.intervals().len())
+        .map(|degree| diatonic_chord(&scale, degree, extension))
+        .collect()
+}
+
+impl fmt::Display for Alterations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Alterations::*;
+        let alteration = match *self {
+            FlatFifth => "♭5",
+            SharpFifth => "♯5",
+            FlatNinth => "♭9",
+            SharpNinth => "♯9",
+            SharpEleventh => "♯11",
+            FlatThirteenth => "♭13",
+        };
+        write!(f, "{}", alteration)
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.root.pitch, self.symbol())?;
+        if let Some(bass) = self.bass {
+            write!(f, "/{}", bass)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseChordErrorKind {
+    Empty,
+    InvalidRoot,
+    InvalidBass,
+    UnexpectedSymbol,
+    ConflictingQuality,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseChordError {
+    pub kind: ParseChordErrorKind,
+    /// Byte range of the offending part of the input.
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseChordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseChordErrorKind::*;
+        let message = match self.kind {
+            Empty => "empty chord symbol",
+            InvalidRoot => "invalid root",
+            InvalidBass => "invalid bass",
+            UnexpectedSymbol => "unexpected symbol",
+            ConflictingQuality => "conflicting chord quality",
+        };
+        write!(f, "{} at {}..{}", message, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseChordError {}
+
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_any(&mut self, prefixes: &[&str]) -> bool {
+        prefixes.iter().any(|prefix| self.eat(prefix))
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let digits = self.rest().len()
+            - self
+                .rest()
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        let number = self.rest()[..digits].parse().ok()?;
+        self.pos += digits;
+        Some(number)
+    }
+
+    fn pitch(&mut self) -> Option<Pitch> {
+        let name = match self.rest().chars().next()? {
+            'C' => NoteName::C,
+            'D' => NoteName::D,
+            'E' => NoteName::E,
+            'F' => NoteName::F,
+            'G' => NoteName::G,
+            'A' => NoteName::A,
+            'B' => NoteName::B,
+            _ => return None,
+        };
+        self.pos += 1;
+
+        let mut accidentals: Accidentals = 0;
+        loop {
+            if self.eat_any(&["#", "♯"]) {
+                accidentals += 1;
+            } else if self.eat_any(&["b", "♭"]) {
+                accidentals -= 1;
+            } else if self.eat_any(&["x", "𝄪"]) {
+                accidentals += 2;
+            } else if self.eat("𝄫") {
+                accidentals -= 2;
+            } else {
+                break;
+            }
+        }
+
+        Some(Pitch { name, accidentals })
+    }
+
+    fn error(&self, kind: ParseChordErrorKind, start: usize) -> ParseChordError {
+        let end = if self.pos > start {
+            self.pos
+        } else {
+            start
+                + self.input[start..]
+                    .chars()
+                    .next()
+                    .map_or(0, |c| c.len_utf8())
+        };
+        ParseChordError {
+            kind,
+            span: start..end,
+        }
+    }
+}
+
+impl Chord {
+    fn extend_to(&mut self, extension: u32, major_seventh: bool) {
+        use Tensions::*;
+        self.tensions
+            .retain(|t| *t != Tensions::Seventh && *t != MajorSeventh);
+        self.tensions.push(if major_seventh {
+            MajorSeventh
+        } else {
+            Tensions::Seventh
+        });
+        if extension >= 9 {
+            self.tensions.push(Tensions::Ninth);
+        }
+        if extension == 11 || (extension == 13 && self.quality == Qualities::Minor) {
+            self.tensions.push(Eleventh);
+        }
+        if extension == 13 {
+            self.tensions.push(Tensions::Thirteenth);
+        }
+    }
+}
+
+/// Parses a chord symbol such as `C#m7b5`, `Bbmaj9#11/D` or `F7alt`. Both ASCII and Unicode
+/// accidentals are accepted, so every symbol printed by `Display` parses back. The root is
+/// placed in octave 4.
+pub fn parse(symbol: &str) -> Result<Chord, ParseChordError> {
+    use ParseChordErrorKind::*;
+
+    let mut cursor = Cursor {
+        input: symbol,
+        pos: 0,
+    };
+    if symbol.is_empty() {
+        return Err(cursor.error(Empty, 0));
+    }
+
+    let root = cursor.pitch().ok_or_else(|| cursor.error(InvalidRoot, 0))?;
+    let mut chord = Chord::new(
+        Note {
+            pitch: root,
+            octave: 4,
+        },
+        Qualities::Major,
+    );
+
+    let mut major_seventh = false;
+    let mut half_diminished = false;
+    if cursor.eat_any(&["maj", "Maj", "MA", "M", "Δ", "∆"]) {
+        major_seventh = true;
+    } else if cursor.eat_any(&["min", "mi", "m", "-"]) {
+        chord.quality = Qualities::Minor;
+        major_seventh = cursor.eat_any(&["maj", "M", "Δ"]);
+    } else if cursor.eat_any(&["dim", "°"])
+        || (!cursor.rest().starts_with("omit") && cursor.eat("o"))
+    {
+        chord.quality = Qualities::Diminshed;
+    } else if cursor.eat("ø") {
+        chord.quality = Qualities::Minor;
+        chord.alterations.push(Alterations::FlatFifth);
+        half_diminished = true;
+    } else if cursor.eat_any(&["aug", "+"]) {
+        chord.quality = Qualities::Augmented;
+    }
+
+    let start = cursor.pos;
+    match cursor.number() {
+        Some(5) if chord.quality == Qualities::Major && !major_seventh => {
+            chord.omissions.push(Omissions::Third);
+        }
+        Some(6) if !major_seventh => {
+            chord.tensions.push(Tensions::Sixth);
+            if cursor.eat_any(&["/9", "9"]) {
+                chord.tensions.push(Tensions::Ninth);
+            }
+        }
+        Some(extension @ (7 | 9 | 11 | 13)) => chord.extend_to(extension, major_seventh),
+        Some(_) => return Err(cursor.error(UnexpectedSymbol, start)),
+        None if half_diminished => chord.extend_to(7, false),
+        None => {}
+    }
+
+    while !cursor.rest().is_empty() {
+        let start = cursor.pos;
+
+        if cursor.eat_any(&["(", ")", ",", " "]) {
+            continue;
+        }
+
+        if cursor.eat("/") {
+            chord.bass = Some(
+                cursor
+                    .pitch()
+                    .ok_or_else(|| cursor.error(InvalidBass, cursor.pos))?,
+            );
+            if !cursor.rest().is_empty() {
+                return Err(cursor.error(UnexpectedSymbol, cursor.pos));
+            }
+            break;
+        }
+
+        if cursor.eat("sus") {
+            let quality = match cursor.number() {
+                None | Some(4) => Qualities::Sus4,
+                Some(2) => Qualities::Sus2,
+                Some(_) => return Err(cursor.error(UnexpectedSymbol, start)),
+            };
+            if chord.quality != Qualities::Major {
+                return Err(cursor.error(ConflictingQuality, start));
+            }
+            chord.quality = quality;
+        } else if cursor.eat("add") {
+            let tension = match cursor.number() {
+                Some(2 | 9) => Tensions::Ninth,
+                Some(4 | 11) => Tensions::Eleventh,
+                Some(6) => Tensions::Sixth,
+                Some(13) => Tensions::Thirteenth,
+                _ => return Err(cursor.error(UnexpectedSymbol, start)),
+            };
+            chord.tensions.push(tension);
+        } else if cursor.eat_any(&["omit", "no"]) {
+            let omission = match cursor.number() {
+                Some(3) => Omissions::Third,
+                Some(5) => Omissions::Fifth,
+                _ => return Err(cursor.error(UnexpectedSymbol, start)),
+            };
+            chord.omissions.push(omission);
+        } else if cursor.eat("alt") {
+            if !chord.tensions.contains(&Tensions::Seventh) {
+                chord.extend_to(7, false);
+            }
+            chord.alterations.extend(ALTERED);
+        } else if cursor.eat_any(&["maj", "M", "Δ"]) {
+            match cursor.number() {
+                Some(extension @ (7 | 9 | 11 | 13)) => chord.extend_to(extension, true),
+                _ => return Err(cursor.error(UnexpectedSymbol, start)),
+            }
+        } else if cursor.eat_any(&["b", "♭", "-", "#", "♯", "+"]) {
+            let flat = matches!(&symbol[start..cursor.pos], "b" | "♭" | "-");
+            let alteration = match (flat, cursor.number()) {
+                (true, Some(5)) => Alterations::FlatFifth,
+                (false, Some(5)) => Alterations::SharpFifth,
+                (true, Some(9)) => Alterations::FlatNinth,
+                (false, Some(9)) => Alterations::SharpNinth,
+                (false, Some(11)) => Alterations::SharpEleventh,
+                (true, Some(13)) => Alterations::FlatThirteenth,
+                _ => return Err(cursor.error(UnexpectedSymbol, start)),
+            };
+            chord.alterations.push(alteration);
+        } else {
+            return Err(cursor.error(UnexpectedSymbol, start));
+        }
+    }
+
+    Ok(chord)
+}
+
+impl FromStr for Chord {
+    type Err = ParseChordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+
+/// A note value with augmentation dots, e.g. a dotted quarter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Duration {
+    /// Fraction of a whole note as its denominator: 1, 2, 4, 8 and so on.
+    pub value: u32,
+    pub dots: u32,
+}
+
+impl Duration {
+    pub const WHOLE: Duration = Duration { value: 1, dots: 0 };
+    pub const HALF: Duration = Duration { value: 2, dots: 0 };
+    pub const QUARTER: Duration = Duration { value: 4, dots: 0 };
+    pub const EIGHTH: Duration = Duration { value: 8, dots: 0 };
+    pub const SIXTEENTH: Duration = Duration { value: 16, dots: 0 };
+
+    pub fn dotted(&self) -> Duration {
+        Duration {
+            dots: self.dots + 1,
+            ..*self
+        }
+    }
+
+    /// Length in ticks at `per_quarter` ticks per quarter note, rounded down if the
+    /// resolution is too coarse. Returns `None` if `value` is 0 or the length does not fit
+    /// in a `u32`.
+    pub fn ticks(&self, per_quarter: u32) -> Option<u32> {
+        let base = per_quarter.checked_mul(4)?.checked_div(self.value)?;
+        (0..=self.dots).try_fold(0u32, |sum, dot| sum.checked_add(base.checked_shr(dot)?))
+    }
+
+    /// Returns the plain or dotted note value, with at most three dots, lasting exactly
+    /// `ticks` at `per_quarter` ticks per quarter note.
+    pub fn from_ticks(ticks: u32, per_quarter: u32) -> Option<Duration> {
+        let base = per_quarter.checked_mul(4)?;
+        (0..=7)
+            .map(|power| 1 << power)
+            .flat_map(|value| (0..=3).map(move |dots| Duration { value, dots }))
+            .find(|d| base.is_multiple_of(d.value << d.dots) && d.ticks(per_quarter) == Some(ticks))
+    }
+}
+
+/// Writes the value followed by its dots, as in LilyPond: `4.` for a dotted quarter.
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, ".".repeat(self.dots as usize))
+    }
+}
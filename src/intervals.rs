@@ -1,5 +1,6 @@
 use std::cmp::max;
 
+/// ```text
 ///             RAISE       LOWER
 /// Minor       Major       Diminshed
 /// Perfect     Augmented   Diminished
@@ -7,7 +8,7 @@ use std::cmp::max;
 ///
 ///         DIM     MIN     PER     MAJ     AUG
 /// uni     x       x       1       -       2
-/// 2nd     0?      1       -       2       3
+/// 2nd     0       1       -       2       3
 /// 3rd     2       3       -       4       5
 /// 4th     4       -       5       -       6
 /// 5th     5       -       7       -       8
@@ -16,7 +17,16 @@ use std::cmp::max;
 ///
 /// 8th     11      -       12      -       13
 /// 9th     12      13      -       14      15
-/// etc?
+/// 10th    14      15      -       16      17
+/// 11th    16      -       17      -       18
+/// 12th    18      -       19      -       20
+/// 13th    19      20      -       21      22
+/// 14th    21      22      -       23      24
+/// 15th    23      -       24      -       25
+///
+/// Wider intervals are expressed as one of the above plus a number of octaves, see
+/// `DirectedInterval`.
+/// ```
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Interval {
@@ -33,6 +43,9 @@ pub enum PerfectInterval {
     Fourth,
     Fifth,
     Octave,
+    Eleventh,
+    Twelfth,
+    Fifteenth,
 }
 
 pub trait Diatonic {
@@ -47,6 +60,9 @@ impl PerfectInterval {
             Fourth => 5,
             Fifth => 7,
             Octave => 12,
+            Eleventh => 17,
+            Twelfth => 19,
+            Fifteenth => 24,
         }
     }
 }
@@ -59,6 +75,9 @@ impl Diatonic for PerfectInterval {
             Fourth => 3,
             Fifth => 4,
             Octave => 7,
+            Eleventh => 10,
+            Twelfth => 11,
+            Fifteenth => 14,
         }
     }
 }
@@ -69,6 +88,10 @@ pub enum ImperfectInterval {
     Third,
     Sixth,
     Seventh,
+    Ninth,
+    Tenth,
+    Thirteenth,
+    Fourteenth,
 }
 
 impl ImperfectInterval {
@@ -79,6 +102,10 @@ impl ImperfectInterval {
             Third => 3,
             Sixth => 8,
             Seventh => 10,
+            Ninth => 13,
+            Tenth => 15,
+            Thirteenth => 20,
+            Fourteenth => 22,
         }
     }
     pub fn major_size(&self) -> u32 {
@@ -94,6 +121,10 @@ impl Diatonic for ImperfectInterval {
             Third => 2,
             Sixth => 5,
             Seventh => 6,
+            Ninth => 8,
+            Tenth => 9,
+            Thirteenth => 12,
+            Fourteenth => 13,
         }
     }
 }
@@ -145,3 +176,131 @@ impl Diatonic for Interval {
         }
     }
 }
+
+impl Interval {
+    /// Returns the interval spanning `steps` letter names and `size` semitones, if it can be
+    /// named with a single quality. `steps` must be at most two octaves.
+    pub fn from_steps(steps: u32, size: u32) -> Option<Interval> {
+        use ImperfectInterval::*;
+        use PerfectInterval::*;
+
+        let degree = match steps {
+            0 => AnyInterval::Perfect(Unison),
+            1 => AnyInterval::Imperfect(Second),
+            2 => AnyInterval::Imperfect(Third),
+            3 => AnyInterval::Perfect(Fourth),
+            4 => AnyInterval::Perfect(Fifth),
+            5 => AnyInterval::Imperfect(Sixth),
+            6 => AnyInterval::Imperfect(Seventh),
+            7 => AnyInterval::Perfect(Octave),
+            8 => AnyInterval::Imperfect(Ninth),
+            9 => AnyInterval::Imperfect(Tenth),
+            10 => AnyInterval::Perfect(Eleventh),
+            11 => AnyInterval::Perfect(Twelfth),
+            12 => AnyInterval::Imperfect(Thirteenth),
+            13 => AnyInterval::Imperfect(Fourteenth),
+            14 => AnyInterval::Perfect(Fifteenth),
+            _ => return None,
+        };
+
+        let size = size as i32;
+        match degree {
+            AnyInterval::Perfect(interval) => match size - interval.size() as i32 {
+                -1 if interval != Unison => Some(Interval::Diminshed(degree)),
+                0 => Some(Interval::Perfect(interval)),
+                1 => Some(Interval::Augmented(degree)),
+                _ => None,
+            },
+            AnyInterval::Imperfect(interval) => match size - interval.major_size() as i32 {
+                -2 => Some(Interval::Diminshed(degree)),
+                -1 => Some(Interval::Minor(interval)),
+                0 => Some(Interval::Major(interval)),
+                1 => Some(Interval::Augmented(degree)),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.diatonic_steps() > 7
+    }
+
+    /// Splits a compound interval into the simple interval and the number of octaves it
+    /// spans, e.g. a major ninth into a major second and one octave. Octaves are kept whole,
+    /// so a perfect fifteenth becomes a perfect octave and one octave.
+    pub fn simple(&self) -> (Interval, u32) {
+        if !self.is_compound() {
+            return (*self, 0);
+        }
+        let octaves = (self.diatonic_steps() - 1) / 7;
+        let simple = Interval::from_steps(
+            self.diatonic_steps() - 7 * octaves,
+            self.size() - 12 * octaves,
+        )
+        .expect("a compound interval reduces to a simple interval of the same quality");
+        (simple, octaves)
+    }
+
+    /// Widens the interval by `octaves`, if the result is at most two octaves wide.
+    pub fn compound(&self, octaves: u32) -> Option<Interval> {
+        Interval::from_steps(
+            self.diatonic_steps() + 7 * octaves,
+            self.size() + 12 * octaves,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+/// An interval together with the direction it is taken in and a number of extra octaves,
+/// as returned by `Note::interval_to`. The extra octaves allow intervals of any width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DirectedInterval {
+    pub interval: Interval,
+    pub octaves: u32,
+    pub direction: Direction,
+}
+
+impl DirectedInterval {
+    /// Signed distance in semitones.
+    pub fn size(&self) -> i32 {
+        let size = (self.interval.size() + 12 * self.octaves) as i32;
+        match self.direction {
+            Direction::Up => size,
+            Direction::Down => -size,
+        }
+    }
+
+    /// Signed distance in letter names.
+    pub fn diatonic_steps(&self) -> i32 {
+        let steps = (self.interval.diatonic_steps() + 7 * self.octaves) as i32;
+        match self.direction {
+            Direction::Up => steps,
+            Direction::Down => -steps,
+        }
+    }
+
+    pub fn inverted(&self) -> DirectedInterval {
+        DirectedInterval {
+            direction: match self.direction {
+                Direction::Up => Direction::Down,
+                Direction::Down => Direction::Up,
+            },
+            ..*self
+        }
+    }
+}
+
+impl From<Interval> for DirectedInterval {
+    fn from(interval: Interval) -> Self {
+        DirectedInterval {
+            interval,
+            octaves: 0,
+            direction: Direction::Up,
+        }
+    }
+}
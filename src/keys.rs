@@ -0,0 +1,171 @@
+use std::{cmp::Reverse, collections::BTreeMap, collections::HashMap, fmt};
+
+use crate::{
+    notes::{Accidentals, Note, NoteName, Octave, Pitch},
+    scales::{spell, Scale},
+};
+
+/// Order in which sharps are added to a key signature. Flats are added in reverse.
+const SHARPS: [NoteName; 7] = [
+    NoteName::F,
+    NoteName::C,
+    NoteName::G,
+    NoteName::D,
+    NoteName::A,
+    NoteName::E,
+    NoteName::B,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySignature {
+    /// Number of sharps, or flats if negative, as counted around the circle of fifths.
+    Standard(i32),
+    /// Accidentals per note name for keys outside the circle of fifths, e.g. F♯ and B♭.
+    /// Names that are not in the map are natural.
+    Custom(BTreeMap<NoteName, Accidentals>),
+}
+
+impl KeySignature {
+    /// Builds a key signature from one pitch per note name. Returns a standard key
+    /// signature if the accidentals match one.
+    pub fn from_pitches<I: IntoIterator<Item = Pitch>>(pitches: I) -> KeySignature {
+        let mut accidentals = BTreeMap::new();
+        for pitch in pitches {
+            accidentals.entry(pitch.name).or_insert(pitch.accidentals);
+        }
+        accidentals.retain(|_, &mut a| a != 0);
+
+        (-7..=7)
+            .map(KeySignature::Standard)
+            .find(|key| {
+                SHARPS
+                    .iter()
+                    .all(|name| key.accidental(*name) == *accidentals.get(name).unwrap_or(&0))
+            })
+            .unwrap_or(KeySignature::Custom(accidentals))
+    }
+
+    /// Derives the key signature of a scale from its spelled notes, e.g. two sharps for
+    /// D major and B minor. Note names missing from the scale are taken to be natural, and
+    /// only the first spelling of a name counts, so C blues has the key signature of C minor
+    /// pentatonic.
+    pub fn from_scale<S: Scale>(scale: S) -> KeySignature {
+        KeySignature::from_pitches(spell(scale).into_iter().map(|note| note.pitch))
+    }
+
+    /// Number of sharps, or flats if negative, for a standard key signature.
+    pub fn fifths(&self) -> Option<i32> {
+        match self {
+            KeySignature::Standard(fifths) => Some(*fifths),
+            KeySignature::Custom(_) => None,
+        }
+    }
+
+    /// Guesses the standard key signature that fits the most of the given pitch classes, for
+    /// music whose key is not known. Ties go to the signature with fewer accidentals, and
+    /// then to sharps.
+    pub fn detect<I: IntoIterator<Item = i32>>(classes: I) -> KeySignature {
+        let mut counts = [0usize; 12];
+        for class in classes {
+            counts[class.rem_euclid(12) as usize] += 1;
+        }
+
+        let fifths = (-7..=7)
+            .max_by_key(|&fifths: &i32| {
+                let in_key: usize = KeySignature::Standard(fifths)
+                    .pitches()
+                    .iter()
+                    .map(|p| counts[p.pitch_class() as usize])
+                    .sum();
+                (in_key, Reverse((fifths.abs(), fifths < 0)))
+            })
+            .unwrap_or(0);
+        KeySignature::Standard(fifths)
+    }
+
+    /// The accidentals every note named `name` carries unless marked otherwise.
+    pub fn accidental(&self, name: NoteName) -> Accidentals {
+        match self {
+            KeySignature::Standard(fifths) => {
+                let (position, count) = if *fifths >= 0 {
+                    (SHARPS.iter().position(|&n| n == name), *fifths)
+                } else {
+                    (SHARPS.iter().rev().position(|&n| n == name), -fifths)
+                };
+                let passes = (count - position.unwrap() as i32 + 6).div_euclid(7);
+                passes.max(0) * fifths.signum()
+            }
+            KeySignature::Custom(accidentals) => *accidentals.get(&name).unwrap_or(&0),
+        }
+    }
+
+    /// The pitch of each note name in the key, starting from C.
+    pub fn pitches(&self) -> Vec<Pitch> {
+        let mut names = SHARPS;
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| Pitch {
+                name,
+                accidentals: self.accidental(name),
+            })
+            .collect()
+    }
+
+    /// Whether `pitch` needs an accidental printed when it appears on its own.
+    pub fn needs_accidental(&self, pitch: &Pitch) -> bool {
+        self.accidental(pitch.name) != pitch.accidentals
+    }
+
+    /// Returns the accidental to print for each note of a measure, or `None` if the key
+    /// signature or an earlier note in the measure already implies it. An accidental holds
+    /// for the rest of the measure on the same note name and octave, so `Some(0)` means a
+    /// natural sign cancelling either one.
+    pub fn printed_accidentals(&self, measure: &[Note]) -> Vec<Option<Accidentals>> {
+        let mut current: HashMap<(NoteName, Octave), Accidentals> = HashMap::new();
+        measure
+            .iter()
+            .map(|note| {
+                let key = (note.pitch.name, note.octave);
+                let implied = *current
+                    .get(&key)
+                    .unwrap_or(&self.accidental(note.pitch.name));
+                current.insert(key, note.pitch.accidentals);
+                (implied != note.pitch.accidentals).then_some(note.pitch.accidentals)
+            })
+            .collect()
+    }
+}
+
+impl Default for KeySignature {
+    fn default() -> Self {
+        KeySignature::Standard(0)
+    }
+}
+
+/// Lists the altered pitches in the order they are written, e.g. "F♯ C♯" for D major, or
+/// "♮" if there are none.
+impl fmt::Display for KeySignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<NoteName> = match self {
+            KeySignature::Standard(fifths) if *fifths < 0 => SHARPS.into_iter().rev().collect(),
+            KeySignature::Standard(_) => SHARPS.into(),
+            KeySignature::Custom(accidentals) => accidentals.keys().copied().collect(),
+        };
+        let pitches: Vec<String> = names
+            .into_iter()
+            .map(|name| Pitch {
+                name,
+                accidentals: self.accidental(name),
+            })
+            .filter(|pitch| pitch.accidentals != 0)
+            .map(|pitch| pitch.to_string())
+            .collect();
+
+        if pitches.is_empty() {
+            write!(f, "♮")
+        } else {
+            write!(f, "{}", pitches.join(" "))
+        }
+    }
+}
@@ -1,3 +1,13 @@
+pub mod chords;
+pub mod durations;
 pub mod intervals;
+pub mod keys;
+pub mod lilypond;
+pub mod midi;
+pub mod musicxml;
 pub mod notes;
+pub mod roman;
 pub mod scales;
+pub mod tuning;
+pub mod voice_leading;
+pub mod voicings;
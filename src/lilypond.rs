@@ -0,0 +1,192 @@
+use std::fmt;
+
+use crate::{
+    chords::{self, Chord},
+    durations::Duration,
+    notes::{Note, Notes, Pitch},
+};
+
+/// How octaves are written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Octaves {
+    /// Every note carries its octave, with `c` for C3 and `c'` for C4.
+    Absolute,
+    /// Inside `\relative c'`, where each note is placed within a fourth of the note before
+    /// and marks only shift it further.
+    Relative,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Clef {
+    Treble,
+    Bass,
+    Alto,
+    Tenor,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    Note(Note),
+    /// Notes sounding together, written as `<c e g>`.
+    Chord(Notes),
+    Rest,
+}
+
+/// Music written as a list of elements with durations, such as a LilyPond score or a
+/// MusicXML part.
+pub trait Staff {
+    fn music_mut(&mut self) -> &mut Vec<(Element, Duration)>;
+
+    /// Appends `notes` one after another, e.g. a spelled scale.
+    fn push_notes(&mut self, notes: &Notes, duration: Duration) {
+        self.music_mut()
+            .extend(notes.0.iter().map(|&note| (Element::Note(note), duration)));
+    }
+
+    /// Appends `notes` sounding together.
+    fn push_chord(&mut self, notes: &Notes, duration: Duration) {
+        self.music_mut()
+            .push((Element::Chord(notes.clone()), duration));
+    }
+
+    /// Appends each chord spelled with `chords::spell`.
+    fn push_chords(&mut self, chords: &[Chord], duration: Duration) {
+        for chord in chords {
+            self.push_chord(&chords::spell(chord), duration);
+        }
+    }
+}
+
+/// A single staff of music that displays as a LilyPond document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Score {
+    pub octaves: Octaves,
+    pub clef: Clef,
+    pub key: Option<(Pitch, Mode)>,
+    pub time: Option<(u8, u8)>,
+    pub music: Vec<(Element, Duration)>,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Score {
+            octaves: Octaves::Relative,
+            clef: Clef::Treble,
+            key: None,
+            time: None,
+            music: vec![],
+        }
+    }
+}
+
+impl Score {
+    /// LilyPond version written at the top of the document.
+    pub const VERSION: &'static str = "2.24.0";
+
+    fn music(&self) -> String {
+        // Diatonic position of the note the next one is placed relative to, C4 to begin with.
+        let mut reference = 4 * 7;
+        let mut note = |note: &Note| {
+            let note = if note.pitch.accidentals.abs() > 2 {
+                note.simplify().unwrap_or(*note)
+            } else {
+                *note
+            };
+            match self.octaves {
+                Octaves::Absolute => note.pitch.dutch_name() + &marks(note.octave as i32 - 3),
+                Octaves::Relative => {
+                    let position = note.diatonic_position();
+                    let nearest = reference + (position - reference + 3).rem_euclid(7) - 3;
+                    reference = position;
+                    note.pitch.dutch_name() + &marks((position - nearest) / 7)
+                }
+            }
+        };
+
+        let mut elements = vec![];
+        for (element, duration) in &self.music {
+            let element = match element {
+                Element::Note(n) => note(n),
+                Element::Chord(notes) => {
+                    let mut notes = notes.clone();
+                    notes.sort_by_pitch();
+                    let first = notes.0.first().copied();
+                    let chord: Vec<String> = notes.0.iter().map(&mut note).collect();
+                    // The note after a chord is placed relative to the chord's first note.
+                    if let Some(first) = first {
+                        note(&first);
+                    }
+                    format!("<{}>", chord.join(" "))
+                }
+                Element::Rest => "r".to_string(),
+            };
+            elements.push(format!("{}{}", element, duration));
+        }
+        elements.join(" ")
+    }
+}
+
+impl Staff for Score {
+    fn music_mut(&mut self) -> &mut Vec<(Element, Duration)> {
+        &mut self.music
+    }
+}
+
+/// Octave marks raising the note by `octaves`, or lowering it if negative.
+/// Respells pitches with more than two accidentals, which LilyPond has no names for.
+fn simplified(pitch: Pitch) -> Pitch {
+    if pitch.accidentals.abs() > 2 {
+        pitch.simplify()
+    } else {
+        pitch
+    }
+}
+
+fn marks(octaves: i32) -> String {
+    if octaves < 0 {
+        ",".repeat(octaves.unsigned_abs() as usize)
+    } else {
+        "'".repeat(octaves as usize)
+    }
+}
+
+/// Writes a complete document that LilyPond can compile, respelling pitches with more than
+/// two accidentals.
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\\version \"{}\"", Score::VERSION)?;
+        writeln!(f)?;
+        match self.octaves {
+            Octaves::Absolute => writeln!(f, "{{")?,
+            Octaves::Relative => writeln!(f, "\\relative c' {{")?,
+        }
+
+        let clef = match self.clef {
+            Clef::Treble => "treble",
+            Clef::Bass => "bass",
+            Clef::Alto => "alto",
+            Clef::Tenor => "tenor",
+        };
+        writeln!(f, "  \\clef {}", clef)?;
+        if let Some((tonic, mode)) = self.key {
+            let mode = match mode {
+                Mode::Major => "major",
+                Mode::Minor => "minor",
+            };
+            writeln!(f, "  \\key {} \\{}", simplified(tonic).dutch_name(), mode)?;
+        }
+        if let Some((numerator, denominator)) = self.time {
+            writeln!(f, "  \\time {}/{}", numerator, denominator)?;
+        }
+        if !self.music.is_empty() {
+            writeln!(f, "  {}", self.music())?;
+        }
+        writeln!(f, "}}")
+    }
+}
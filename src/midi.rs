@@ -0,0 +1,594 @@
+use std::{fmt, io, ops::Range};
+
+use crate::{
+    keys::KeySignature,
+    notes::{Note, Notes, Spelling},
+};
+
+/// Layout of the tracks in a Standard MIDI File.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// Type 0: every track is merged into a single one.
+    Single,
+    /// Type 1: a track with the tempo, time and key signature, followed by one track per
+    /// `Track`, all played at once.
+    Parallel,
+}
+
+/// A note sounding from `start` for `duration`, both in ticks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Event {
+    pub note: Note,
+    pub start: u32,
+    pub duration: u32,
+    pub velocity: u8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Track {
+    /// Written as a track name meta event unless empty.
+    pub name: String,
+    /// MIDI channel from 0 to 15.
+    pub channel: u8,
+    pub events: Vec<Event>,
+}
+
+impl Track {
+    pub const VELOCITY: u8 = 100;
+
+    /// Appends `notes` one after another, each lasting `duration` ticks, e.g. to play a
+    /// spelled scale.
+    pub fn push_melody(&mut self, notes: &Notes, duration: u32) {
+        for &note in &notes.0 {
+            let start = self.end();
+            self.events.push(Event {
+                note,
+                start,
+                duration,
+                velocity: Track::VELOCITY,
+            });
+        }
+    }
+
+    /// Appends `notes` sounding together for `duration` ticks, e.g. to play a spelled chord.
+    pub fn push_chord(&mut self, notes: &Notes, duration: u32) {
+        let start = self.end();
+        self.events.extend(notes.0.iter().map(|&note| Event {
+            note,
+            start,
+            duration,
+            velocity: Track::VELOCITY,
+        }));
+    }
+
+    /// The tick at which the last note ends, at most `u32::MAX`.
+    pub fn end(&self) -> u32 {
+        self.events
+            .iter()
+            .map(|e| e.start.saturating_add(e.duration))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MidiFile {
+    pub format: Format,
+    /// Ticks per quarter note.
+    pub division: u16,
+    /// Microseconds per quarter note, from 1 to `MidiFile::MAX_TEMPO`.
+    pub tempo: u32,
+    /// Numerator and denominator, which must be a power of two.
+    pub time_signature: (u8, u8),
+    /// Written only for standard key signatures, see `KeySignature::fifths`.
+    pub key_signature: Option<KeySignature>,
+    pub minor: bool,
+    pub tracks: Vec<Track>,
+}
+
+impl Default for MidiFile {
+    /// An empty type 1 file at 120 beats per minute in 4/4.
+    fn default() -> Self {
+        MidiFile {
+            format: Format::Parallel,
+            division: 480,
+            tempo: 500_000,
+            time_signature: (4, 4),
+            key_signature: None,
+            minor: false,
+            tracks: vec![],
+        }
+    }
+}
+
+impl MidiFile {
+    /// The slowest tempo a set tempo event can hold, in microseconds per quarter note.
+    pub const MAX_TEMPO: u32 = 0xFF_FFFF;
+
+    /// Sets the tempo in quarter notes per minute. Returns `false`, leaving the tempo
+    /// unchanged, if `bpm` is not a finite number that fits the tempo range, which starts
+    /// at about 3.58.
+    pub fn set_bpm(&mut self, bpm: f64) -> bool {
+        let tempo = (60_000_000.0 / bpm).round();
+        if !bpm.is_finite() || bpm <= 0.0 || !(1.0..=MidiFile::MAX_TEMPO as f64).contains(&tempo) {
+            return false;
+        }
+        self.tempo = tempo as u32;
+        true
+    }
+
+    /// Encodes the file. Returns `None` if a note lies outside the MIDI range, a channel is
+    /// above 15, the tempo is out of range, the time signature's denominator is not a power
+    /// of two, or notes are too far apart or end after tick `u32::MAX`.
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        let (numerator, denominator) = self.time_signature;
+        if !denominator.is_power_of_two() || !(1..=MidiFile::MAX_TEMPO).contains(&self.tempo) {
+            return None;
+        }
+
+        let mut meta = vec![(0, vec![0xFF, 0x51, 0x03]), (0, vec![0xFF, 0x58, 0x04])];
+        meta[0].1.extend(&self.tempo.to_be_bytes()[1..]);
+        meta[1]
+            .1
+            .extend([numerator, denominator.trailing_zeros() as u8, 24, 8]);
+        if let Some(fifths) = self.key_signature.as_ref().and_then(KeySignature::fifths) {
+            meta.push((
+                0,
+                vec![
+                    0xFF,
+                    0x59,
+                    0x02,
+                    fifths.clamp(-7, 7) as i8 as u8,
+                    self.minor as u8,
+                ],
+            ));
+        }
+
+        let mut chunks = vec![];
+        match self.format {
+            Format::Single => {
+                let mut messages = meta;
+                for track in &self.tracks {
+                    messages.extend(track_messages(track)?);
+                }
+                chunks.push(encode_track(messages)?);
+            }
+            Format::Parallel => {
+                chunks.push(encode_track(meta)?);
+                for track in &self.tracks {
+                    chunks.push(encode_track(track_messages(track)?)?);
+                }
+            }
+        }
+
+        let format: u16 = match self.format {
+            Format::Single => 0,
+            Format::Parallel => 1,
+        };
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend(6u32.to_be_bytes());
+        bytes.extend(format.to_be_bytes());
+        bytes.extend((chunks.len() as u16).to_be_bytes());
+        bytes.extend(self.division.to_be_bytes());
+        for chunk in chunks {
+            bytes.extend(b"MTrk");
+            bytes.extend((chunk.len() as u32).to_be_bytes());
+            bytes.extend(chunk);
+        }
+        Some(bytes)
+    }
+
+    /// Writes the file, see `to_bytes`.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let bytes = self.to_bytes().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "cannot encode MIDI file")
+        })?;
+        writer.write_all(&bytes)
+    }
+}
+
+/// The messages of a track with their absolute time in ticks, unsorted.
+fn track_messages(track: &Track) -> Option<Vec<(u32, Vec<u8>)>> {
+    if track.channel > 15 {
+        return None;
+    }
+
+    let mut messages = vec![];
+    if !track.name.is_empty() {
+        let mut name = vec![0xFF, 0x03];
+        name.extend(variable_length(track.name.len() as u32));
+        name.extend(track.name.as_bytes());
+        messages.push((0, name));
+    }
+    for event in &track.events {
+        let key = event.note.to_midi()?;
+        let velocity = event.velocity.min(127);
+        messages.push((event.start, vec![0x90 | track.channel, key, velocity]));
+        messages.push((
+            event.start.checked_add(event.duration)?,
+            vec![0x80 | track.channel, key, 0],
+        ));
+    }
+    Some(messages)
+}
+
+/// Encodes messages as a track chunk body with delta times and an end of track event. Meta
+/// events come first at each tick and note offs before note ons, so repeated notes sound.
+/// Returns `None` if two messages are further apart than a delta time can hold.
+fn encode_track(mut messages: Vec<(u32, Vec<u8>)>) -> Option<Vec<u8>> {
+    messages.sort_by_key(|(tick, message)| {
+        let order = match message[0] & 0xF0 {
+            0xF0 => 0,
+            0x80 => 1,
+            _ => 2,
+        };
+        (*tick, order)
+    });
+
+    let mut bytes = vec![];
+    let mut now = 0;
+    for (tick, message) in &messages {
+        if tick - now > 0x0FFF_FFFF {
+            return None;
+        }
+        bytes.extend(variable_length(tick - now));
+        bytes.extend(message);
+        now = *tick;
+    }
+    bytes.extend([0x00, 0xFF, 0x2F, 0x00]);
+    Some(bytes)
+}
+
+/// Encodes a number in seven-bit groups, most significant first, with the high bit set on
+/// all but the last byte.
+fn variable_length(mut value: u32) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseMidiErrorKind {
+    UnexpectedEnd,
+    InvalidHeader,
+    UnsupportedFormat,
+    UnsupportedDivision,
+    InvalidEvent,
+    /// A key below C0, which `Note` cannot represent.
+    UnsupportedKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMidiError {
+    pub kind: ParseMidiErrorKind,
+    /// Byte range of the offending part of the input.
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseMidiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseMidiErrorKind::*;
+        let message = match self.kind {
+            UnexpectedEnd => "unexpected end of file",
+            InvalidHeader => "invalid header",
+            UnsupportedFormat => "unsupported format",
+            UnsupportedDivision => "unsupported time division",
+            InvalidEvent => "invalid event",
+            UnsupportedKey => "unsupported key",
+        };
+        write!(f, "{} at {}..{}", message, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseMidiError {}
+
+/// Reads big-endian numbers and variable-length quantities from the input.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, kind: ParseMidiErrorKind, start: usize) -> ParseMidiError {
+        ParseMidiError {
+            kind,
+            span: start..self.pos.max(start + 1),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ParseMidiError> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(ParseMidiError {
+                kind: ParseMidiErrorKind::UnexpectedEnd,
+                span: self.pos..self.bytes.len(),
+            })?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ParseMidiError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ParseMidiError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, ParseMidiError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn variable_length(&mut self) -> Result<u32, ParseMidiError> {
+        let start = self.pos;
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(self.error(ParseMidiErrorKind::InvalidEvent, start))
+    }
+}
+
+/// A note received from a track before it is spelled.
+struct Received {
+    key: u8,
+    channel: u8,
+    start: u32,
+    duration: u32,
+    velocity: u8,
+    /// Byte offset of the note on event, for errors.
+    offset: usize,
+}
+
+/// The contents of a track chunk.
+#[derive(Default)]
+struct Chunk {
+    name: String,
+    notes: Vec<Received>,
+    /// Tempo, time signature and key signature events with their tick.
+    tempo: Vec<(u32, u32)>,
+    time_signatures: Vec<(u32, (u8, u8))>,
+    key_signatures: Vec<(u32, (i32, bool))>,
+}
+
+fn read_chunk(reader: &mut Reader, end: usize) -> Result<Chunk, ParseMidiError> {
+    use ParseMidiErrorKind::*;
+
+    let mut chunk = Chunk::default();
+    let mut open: Vec<Received> = vec![];
+    let mut now: u32 = 0;
+    let mut running = None;
+
+    while reader.pos < end {
+        let delta = reader.pos;
+        now = now
+            .checked_add(reader.variable_length()?)
+            .ok_or_else(|| reader.error(InvalidEvent, delta))?;
+        let start = reader.pos;
+        let mut status = reader.u8()?;
+        let mut first = None;
+        if status < 0x80 {
+            first = Some(status);
+            status = running.ok_or_else(|| reader.error(InvalidEvent, start))?;
+        }
+
+        match status {
+            0xFF => {
+                running = None;
+                let kind = reader.u8()?;
+                let len = reader.variable_length()? as usize;
+                let data = reader.take(len)?;
+                match (kind, data) {
+                    (0x2F, _) => break,
+                    (0x03, _) => chunk.name = String::from_utf8_lossy(data).into_owned(),
+                    (0x51, [a, b, c]) => {
+                        chunk.tempo.push((now, u32::from_be_bytes([0, *a, *b, *c])))
+                    }
+                    (0x58, [numerator, denominator, ..]) => chunk.time_signatures.push((
+                        now,
+                        (
+                            *numerator,
+                            1u8.checked_shl(*denominator as u32).unwrap_or(0),
+                        ),
+                    )),
+                    (0x59, [fifths, minor]) => chunk
+                        .key_signatures
+                        .push((now, (*fifths as i8 as i32, *minor != 0))),
+                    (0x51 | 0x58 | 0x59, _) => return Err(reader.error(InvalidEvent, start)),
+                    _ => {}
+                }
+            }
+            0xF0 | 0xF7 => {
+                running = None;
+                let len = reader.variable_length()? as usize;
+                reader.take(len)?;
+            }
+            0x80..=0xEF => {
+                running = Some(status);
+                let channel = status & 0x0F;
+                let len = match status & 0xF0 {
+                    0xC0 | 0xD0 => 1,
+                    _ => 2,
+                };
+                let mut data = vec![];
+                data.extend(first);
+                while data.len() < len {
+                    data.push(reader.u8()?);
+                }
+                if data.iter().any(|&b| b >= 0x80) {
+                    return Err(reader.error(InvalidEvent, start));
+                }
+
+                match (status & 0xF0, data.as_slice()) {
+                    (0x90, &[key, velocity]) if velocity > 0 => open.push(Received {
+                        key,
+                        channel,
+                        start: now,
+                        duration: 0,
+                        velocity,
+                        offset: start,
+                    }),
+                    (0x80 | 0x90, &[key, _]) => {
+                        if let Some(i) = open
+                            .iter()
+                            .position(|n| n.key == key && n.channel == channel)
+                        {
+                            let mut note = open.remove(i);
+                            note.duration = now - note.start;
+                            chunk.notes.push(note);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => return Err(reader.error(InvalidEvent, start)),
+        }
+    }
+
+    // Notes still sounding at the end of the track last until it ends.
+    for mut note in open {
+        note.duration = now - note.start;
+        chunk.notes.push(note);
+    }
+    reader.pos = end;
+    Ok(chunk)
+}
+
+/// The value of the event with the lowest tick.
+fn earliest<T, I: Iterator<Item = (u32, T)>>(events: I) -> Option<T> {
+    events.min_by_key(|(tick, _)| *tick).map(|(_, value)| value)
+}
+
+impl MidiFile {
+    /// Decodes a type 0 or type 1 file. Each channel of each track chunk becomes a `Track`,
+    /// with events ordered by start and pitch, and chunks without notes are left out.
+    ///
+    /// Keys are spelled with the key signature in effect when the note starts. Files
+    /// without key signature events are spelled in the key detected from all their notes,
+    /// see `KeySignature::detect`. The first tempo, time signature and key signature of the
+    /// file are kept.
+    ///
+    /// Keys below 12 lie below C0 and cannot be a `Note`, so a file that plays one fails to
+    /// decode with `UnsupportedKey`. So does a file whose events lie beyond tick `u32::MAX`,
+    /// with `InvalidEvent`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<MidiFile, ParseMidiError> {
+        use ParseMidiErrorKind::*;
+
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4).map_err(|e| ParseMidiError {
+            kind: InvalidHeader,
+            ..e
+        })? != b"MThd"
+        {
+            return Err(reader.error(InvalidHeader, 0));
+        }
+        let header_len = reader.u32()? as usize;
+        if header_len < 6 {
+            return Err(reader.error(InvalidHeader, 4));
+        }
+        let header_end = reader.pos + header_len;
+        let format = match reader.u16()? {
+            0 => Format::Single,
+            1 => Format::Parallel,
+            _ => return Err(reader.error(UnsupportedFormat, 8)),
+        };
+        reader.u16()?;
+        let division = reader.u16()?;
+        if division & 0x8000 != 0 {
+            return Err(reader.error(UnsupportedDivision, 12));
+        }
+        reader.pos = header_end;
+
+        let mut chunks = vec![];
+        while reader.pos < bytes.len() {
+            let kind = reader.take(4)?;
+            let len = reader.u32()? as usize;
+            let end = reader.pos + len;
+            if end > bytes.len() {
+                return Err(reader.error(UnexpectedEnd, reader.pos));
+            }
+            if kind == b"MTrk" {
+                chunks.push(read_chunk(&mut reader, end)?);
+            }
+            reader.pos = end;
+        }
+
+        let mut key_signatures: Vec<(u32, (i32, bool))> = chunks
+            .iter()
+            .flat_map(|c| c.key_signatures.iter().copied())
+            .collect();
+        key_signatures.sort_by_key(|(tick, _)| *tick);
+        let detected = KeySignature::detect(
+            chunks
+                .iter()
+                .flat_map(|c| c.notes.iter().map(|n| n.key as i32)),
+        );
+
+        let mut file = MidiFile {
+            format,
+            division,
+            tempo: earliest(chunks.iter().flat_map(|c| c.tempo.iter().copied())).unwrap_or(500_000),
+            time_signature: earliest(
+                chunks
+                    .iter()
+                    .flat_map(|c| c.time_signatures.iter().copied()),
+            )
+            .unwrap_or((4, 4)),
+            key_signature: key_signatures
+                .first()
+                .map(|(_, (fifths, _))| KeySignature::Standard(*fifths)),
+            minor: key_signatures.first().is_some_and(|(_, (_, minor))| *minor),
+            tracks: vec![],
+        };
+
+        for chunk in chunks {
+            let mut channels: Vec<u8> = chunk.notes.iter().map(|n| n.channel).collect();
+            channels.sort();
+            channels.dedup();
+
+            for channel in channels {
+                let mut track = Track {
+                    name: chunk.name.clone(),
+                    channel,
+                    events: vec![],
+                };
+                for note in chunk.notes.iter().filter(|n| n.channel == channel) {
+                    let key = key_signatures
+                        .iter()
+                        .rev()
+                        .find(|(tick, _)| *tick <= note.start)
+                        .map_or(detected.clone(), |(_, (fifths, _))| {
+                            KeySignature::Standard(*fifths)
+                        });
+                    let spelled = Note::from_midi(note.key, &Spelling::Signature(key)).ok_or(
+                        ParseMidiError {
+                            kind: UnsupportedKey,
+                            span: note.offset..note.offset + 3,
+                        },
+                    )?;
+                    track.events.push(Event {
+                        note: spelled,
+                        start: note.start,
+                        duration: note.duration,
+                        velocity: note.velocity,
+                    });
+                }
+                track
+                    .events
+                    .sort_by(|a, b| a.start.cmp(&b.start).then(a.note.cmp_pitch(&b.note)));
+                file.tracks.push(track);
+            }
+        }
+
+        Ok(file)
+    }
+}
@@ -0,0 +1,1134 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    ops::Range,
+    str::FromStr,
+};
+
+use crate::{
+    chords::{self, Alterations, Chord, Omissions},
+    durations::Duration,
+    keys::KeySignature,
+    lilypond::{Element, Staff},
+    notes::{Accidentals, Note, NoteName, Notes, Pitch},
+    scales::{Qualities, Tensions},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Part {
+    pub name: String,
+    pub music: Vec<(Element, Duration)>,
+    /// Chord symbols, each with the index of the element in `music` it is written above.
+    pub harmony: Vec<(usize, Chord)>,
+}
+
+impl Staff for Part {
+    fn music_mut(&mut self) -> &mut Vec<(Element, Duration)> {
+        &mut self.music
+    }
+
+    /// Appends each chord spelled with `chords::spell`, with its chord symbol above.
+    fn push_chords(&mut self, chords: &[Chord], duration: Duration) {
+        for chord in chords {
+            self.harmony.push((self.music.len(), chord.clone()));
+            self.push_chord(&chords::spell(chord), duration);
+        }
+    }
+}
+
+/// A partwise MusicXML 4.0 score, written with `to_xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Score {
+    /// Divisions per quarter note, the unit of `<duration>`.
+    pub divisions: u32,
+    pub key: Option<KeySignature>,
+    pub minor: bool,
+    pub time: (u8, u8),
+    pub parts: Vec<Part>,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Score {
+            divisions: 480,
+            key: None,
+            minor: false,
+            time: (4, 4),
+            parts: vec![],
+        }
+    }
+}
+
+/// One note, chord or rest as written in a measure. Elements that cross a barline or
+/// cannot be written as a single note value are split into pieces tied together.
+struct Piece<'a> {
+    element: &'a Element,
+    ticks: u32,
+    duration: Duration,
+    tie_start: bool,
+    tie_stop: bool,
+    harmony: Vec<&'a Chord>,
+}
+
+impl Score {
+    /// Length of a measure in divisions, or `None` if the time signature has no beats, a
+    /// beat type that is not a power of two, or does not last a whole number of divisions.
+    fn measure_ticks(&self) -> Option<u32> {
+        let (beats, beat_type) = (self.time.0 as u32, self.time.1 as u32);
+        if beats == 0 || !beat_type.is_power_of_two() {
+            return None;
+        }
+        let ticks = self.divisions.checked_mul(4)?.checked_mul(beats)?;
+        (ticks > 0 && ticks.is_multiple_of(beat_type)).then_some(ticks / beat_type)
+    }
+
+    fn measures<'a>(&self, part: &'a Part, measure: u32) -> Vec<Vec<Piece<'a>>> {
+        let mut measures = vec![vec![]];
+        let mut filled = 0;
+
+        for (index, (element, duration)) in part.music.iter().enumerate() {
+            // `to_xml` checks that every duration has a length.
+            let total = duration.ticks(self.divisions).unwrap_or(0);
+            let mut durations = vec![];
+            let mut remaining = total;
+            while remaining > 0 {
+                if filled == measure {
+                    measures.push(vec![]);
+                    filled = 0;
+                }
+                let ticks = remaining.min(measure - filled);
+                if ticks == total {
+                    durations.push((measures.len() - 1, *duration));
+                } else {
+                    for duration in note_values(ticks, self.divisions) {
+                        durations.push((measures.len() - 1, duration));
+                    }
+                }
+                filled += ticks;
+                remaining -= ticks;
+            }
+
+            let tied = !matches!(element, Element::Rest);
+            let count = durations.len();
+            for (i, (measure, duration)) in durations.into_iter().enumerate() {
+                measures[measure].push(Piece {
+                    element,
+                    ticks: duration.ticks(self.divisions).unwrap_or(0),
+                    duration,
+                    tie_start: tied && i + 1 < count,
+                    tie_stop: tied && i > 0,
+                    harmony: match i {
+                        0 => part
+                            .harmony
+                            .iter()
+                            .filter(|(at, _)| *at == index)
+                            .map(|(_, chord)| chord)
+                            .collect(),
+                        _ => vec![],
+                    },
+                });
+            }
+        }
+        measures
+    }
+
+    fn write_attributes(&self, out: &mut String, part: &Part) -> fmt::Result {
+        writeln!(out, "      <attributes>")?;
+        writeln!(out, "        <divisions>{}</divisions>", self.divisions)?;
+        match &self.key {
+            Some(KeySignature::Standard(fifths)) => {
+                let mode = if self.minor { "minor" } else { "major" };
+                writeln!(
+                    out,
+                    "        <key><fifths>{}</fifths><mode>{}</mode></key>",
+                    fifths, mode
+                )?;
+            }
+            Some(KeySignature::Custom(accidentals)) => {
+                write!(out, "        <key>")?;
+                for (name, alter) in accidentals {
+                    write!(
+                        out,
+                        "<key-step>{}</key-step><key-alter>{}</key-alter>",
+                        step(*name),
+                        alter
+                    )?;
+                }
+                writeln!(out, "</key>")?;
+            }
+            None => {}
+        }
+        writeln!(
+            out,
+            "        <time><beats>{}</beats><beat-type>{}</beat-type></time>",
+            self.time.0, self.time.1
+        )?;
+
+        let positions: Vec<i32> = part
+            .music
+            .iter()
+            .flat_map(|(element, _)| match element {
+                Element::Note(note) => vec![*note],
+                Element::Chord(notes) => notes.0.clone(),
+                Element::Rest => vec![],
+            })
+            .map(|note| note.position())
+            .collect();
+        let average = positions.iter().sum::<i32>() / (positions.len().max(1) as i32);
+        if positions.is_empty() || average >= 4 * 12 {
+            writeln!(out, "        <clef><sign>G</sign><line>2</line></clef>")?;
+        } else {
+            writeln!(out, "        <clef><sign>F</sign><line>4</line></clef>")?;
+        }
+        writeln!(out, "      </attributes>")
+    }
+
+    fn write_part(&self, out: &mut String, part: &Part, measure: u32) -> fmt::Result {
+        let key = self.key.clone().unwrap_or_default();
+
+        for (number, pieces) in self.measures(part, measure).iter().enumerate() {
+            writeln!(out, "    <measure number=\"{}\">", number + 1)?;
+            if number == 0 {
+                self.write_attributes(out, part)?;
+            }
+
+            // Tied continuations keep the accidental of the note they continue.
+            let sounding: Vec<Note> = pieces
+                .iter()
+                .filter(|piece| !piece.tie_stop)
+                .flat_map(|piece| notes_of(piece.element))
+                .collect();
+            let mut accidentals = key.printed_accidentals(&sounding).into_iter();
+
+            for piece in pieces {
+                for chord in &piece.harmony {
+                    write_harmony(out, chord)?;
+                }
+                let notes = notes_of(piece.element);
+                if notes.is_empty() {
+                    write_note(out, None, false, piece, None)?;
+                }
+                for (i, note) in notes.iter().enumerate() {
+                    let accidental = match piece.tie_stop {
+                        true => None,
+                        false => accidentals.next().flatten(),
+                    };
+                    write_note(out, Some(note), i > 0, piece, accidental)?;
+                }
+            }
+            writeln!(out, "    </measure>")?;
+        }
+        Ok(())
+    }
+}
+
+/// The notes of an element from low to high.
+fn notes_of(element: &Element) -> Vec<Note> {
+    match element {
+        Element::Note(note) => vec![*note],
+        Element::Chord(notes) => {
+            let mut notes = notes.clone();
+            notes.sort_by_pitch();
+            notes.0
+        }
+        Element::Rest => vec![],
+    }
+}
+
+/// Splits `ticks` into plain note values, longest first.
+fn note_values(mut ticks: u32, divisions: u32) -> Vec<Duration> {
+    let mut durations = vec![];
+    for value in (0..=7).map(|power| 1 << power) {
+        let duration = Duration { value, dots: 0 };
+        let length = duration.ticks(divisions).unwrap_or(0);
+        while length > 0 && length <= ticks {
+            durations.push(duration);
+            ticks -= length;
+        }
+    }
+    durations
+}
+
+fn step(name: NoteName) -> &'static str {
+    match name {
+        NoteName::C => "C",
+        NoteName::D => "D",
+        NoteName::E => "E",
+        NoteName::F => "F",
+        NoteName::G => "G",
+        NoteName::A => "A",
+        NoteName::B => "B",
+    }
+}
+
+fn note_type(duration: Duration) -> &'static str {
+    match duration.value {
+        1 => "whole",
+        2 => "half",
+        4 => "quarter",
+        8 => "eighth",
+        16 => "16th",
+        32 => "32nd",
+        64 => "64th",
+        _ => "128th",
+    }
+}
+
+fn accidental_name(accidentals: Accidentals) -> Option<&'static str> {
+    match accidentals {
+        -3 => Some("triple-flat"),
+        -2 => Some("flat-flat"),
+        -1 => Some("flat"),
+        0 => Some("natural"),
+        1 => Some("sharp"),
+        2 => Some("double-sharp"),
+        3 => Some("triple-sharp"),
+        _ => None,
+    }
+}
+
+fn write_note(
+    out: &mut String,
+    note: Option<&Note>,
+    chord: bool,
+    piece: &Piece,
+    accidental: Option<Accidentals>,
+) -> fmt::Result {
+    writeln!(out, "      <note>")?;
+    if chord {
+        writeln!(out, "        <chord/>")?;
+    }
+    match note {
+        Some(note) => {
+            write!(out, "        <pitch><step>{}</step>", step(note.pitch.name))?;
+            if note.pitch.accidentals != 0 {
+                write!(out, "<alter>{}</alter>", note.pitch.accidentals)?;
+            }
+            writeln!(out, "<octave>{}</octave></pitch>", note.octave)?;
+        }
+        None => writeln!(out, "        <rest/>")?,
+    }
+    writeln!(out, "        <duration>{}</duration>", piece.ticks)?;
+    if piece.tie_stop {
+        writeln!(out, "        <tie type=\"stop\"/>")?;
+    }
+    if piece.tie_start {
+        writeln!(out, "        <tie type=\"start\"/>")?;
+    }
+    writeln!(out, "        <voice>1</voice>")?;
+    writeln!(out, "        <type>{}</type>", note_type(piece.duration))?;
+    for _ in 0..piece.duration.dots {
+        writeln!(out, "        <dot/>")?;
+    }
+    if let Some(name) = accidental.and_then(accidental_name) {
+        writeln!(out, "        <accidental>{}</accidental>", name)?;
+    }
+    if piece.tie_start || piece.tie_stop {
+        write!(out, "        <notations>")?;
+        if piece.tie_stop {
+            write!(out, "<tied type=\"stop\"/>")?;
+        }
+        if piece.tie_start {
+            write!(out, "<tied type=\"start\"/>")?;
+        }
+        writeln!(out, "</notations>")?;
+    }
+    writeln!(out, "      </note>")
+}
+
+/// A chord tone as a MusicXML `<degree>`: value, alteration and type.
+type Degree = (u32, i32, &'static str);
+
+/// The MusicXML chord kind closest to `chord`, and the degrees added, altered or
+/// subtracted from it.
+fn kind(chord: &Chord) -> (&'static str, Vec<Degree>) {
+    use Tensions::*;
+
+    let has = |tension| chord.tensions.contains(&tension);
+    let extension = [Tensions::Thirteenth, Eleventh, Tensions::Ninth]
+        .into_iter()
+        .position(has)
+        .map_or(0, |i| [13, 11, 9][i]);
+    let extended = |names: [&'static str; 4]| match extension {
+        13 => names[3],
+        11 => names[2],
+        9 => names[1],
+        _ => names[0],
+    };
+    let half_diminished = chord.quality == Qualities::Minor
+        && has(Tensions::Seventh)
+        && extension == 0
+        && chord.alterations.contains(&Alterations::FlatFifth);
+    let power = chord.quality == Qualities::Major
+        && chord.omissions.contains(&Omissions::Third)
+        && chord.tensions.is_empty()
+        && chord.alterations.is_empty();
+
+    let seventh = has(Tensions::Seventh) || has(MajorSeventh);
+    let (kind, covered): (&'static str, Vec<Tensions>) = match chord.quality {
+        Qualities::Major if power => ("power", vec![]),
+        Qualities::Major if has(Tensions::Seventh) => (
+            extended([
+                "dominant",
+                "dominant-ninth",
+                "dominant-11th",
+                "dominant-13th",
+            ]),
+            vec![Tensions::Seventh],
+        ),
+        Qualities::Major if has(MajorSeventh) => (
+            extended(["major-seventh", "major-ninth", "major-11th", "major-13th"]),
+            vec![MajorSeventh],
+        ),
+        Qualities::Major if has(Sixth) => ("major-sixth", vec![Sixth]),
+        Qualities::Major => ("major", vec![]),
+        Qualities::Minor if half_diminished => ("half-diminished", vec![Tensions::Seventh]),
+        Qualities::Minor if has(Tensions::Seventh) => (
+            extended(["minor-seventh", "minor-ninth", "minor-11th", "minor-13th"]),
+            vec![Tensions::Seventh],
+        ),
+        Qualities::Minor if has(MajorSeventh) => ("major-minor", vec![MajorSeventh]),
+        Qualities::Minor if has(Sixth) => ("minor-sixth", vec![Sixth]),
+        Qualities::Minor => ("minor", vec![]),
+        Qualities::Diminshed if has(Tensions::Seventh) => {
+            ("diminished-seventh", vec![Tensions::Seventh])
+        }
+        Qualities::Diminshed => ("diminished", vec![]),
+        Qualities::Augmented if has(Tensions::Seventh) => {
+            ("augmented-seventh", vec![Tensions::Seventh])
+        }
+        Qualities::Augmented => ("augmented", vec![]),
+        Qualities::Sus4 => ("suspended-fourth", vec![]),
+        Qualities::Sus2 => ("suspended-second", vec![]),
+    };
+    // Extensions are part of the kind only on top of a seventh that is part of it.
+    let kind_extension = match covered.is_empty() || !seventh {
+        true => 0,
+        false => extension,
+    };
+
+    let mut degrees = vec![];
+    for tension in &chord.tensions {
+        let (value, alter) = match tension {
+            Sixth => (6, 0),
+            Tensions::Seventh if chord.quality == Qualities::Diminshed => (7, -2),
+            Tensions::Seventh => (7, -1),
+            MajorSeventh => (7, 0),
+            Tensions::Ninth => (9, 0),
+            Eleventh => (11, 0),
+            Tensions::Thirteenth => (13, 0),
+        };
+        let implied = value > 7 && value <= kind_extension;
+        if !covered.contains(tension) && !implied {
+            degrees.push((value, alter, "add"));
+        }
+    }
+    for alteration in &chord.alterations {
+        if half_diminished && *alteration == Alterations::FlatFifth {
+            continue;
+        }
+        let (value, alter) = match alteration {
+            Alterations::FlatFifth => (5, -1),
+            Alterations::SharpFifth => (5, 1),
+            Alterations::FlatNinth => (9, -1),
+            Alterations::SharpNinth => (9, 1),
+            Alterations::SharpEleventh => (11, 1),
+            Alterations::FlatThirteenth => (13, -1),
+        };
+        let kind = match value == 5 || value <= kind_extension {
+            true => "alter",
+            false => "add",
+        };
+        degrees.push((value, alter, kind));
+    }
+    for omission in &chord.omissions {
+        match omission {
+            Omissions::Third if power => {}
+            Omissions::Third => degrees.push((3, 0, "subtract")),
+            Omissions::Fifth => degrees.push((5, 0, "subtract")),
+        }
+    }
+    (kind, degrees)
+}
+
+fn write_harmony(out: &mut String, chord: &Chord) -> fmt::Result {
+    let (kind, degrees) = kind(chord);
+    let pitch = |out: &mut String, element: &str, pitch: Pitch| {
+        write!(out, "<{0}-step>{1}</{0}-step>", element, step(pitch.name))?;
+        if pitch.accidentals != 0 {
+            write!(
+                out,
+                "<{0}-alter>{1}</{0}-alter>",
+                element, pitch.accidentals
+            )?;
+        }
+        Ok(())
+    };
+
+    writeln!(out, "      <harmony>")?;
+    write!(out, "        <root>")?;
+    pitch(out, "root", chord.root.pitch)?;
+    writeln!(out, "</root>")?;
+    writeln!(
+        out,
+        "        <kind text=\"{}\">{}</kind>",
+        escape(&chord.symbol()),
+        kind
+    )?;
+    if let Some(bass) = chord.bass {
+        write!(out, "        <bass>")?;
+        pitch(out, "bass", bass)?;
+        writeln!(out, "</bass>")?;
+    }
+    for (value, alter, kind) in degrees {
+        writeln!(
+            out,
+            "        <degree><degree-value>{}</degree-value><degree-alter>{}</degree-alter>\
+             <degree-type>{}</degree-type></degree>",
+            value, alter, kind
+        )?;
+    }
+    writeln!(out, "      </harmony>")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl Score {
+    /// Writes the score as a MusicXML document. Returns `None` if the time signature cannot
+    /// be measured in `divisions`, see `measure_ticks`, or a note lasts less than one
+    /// division or has no length at all, see `Duration::ticks`.
+    pub fn to_xml(&self) -> Option<String> {
+        let measure = self.measure_ticks()?;
+        let too_short = self
+            .parts
+            .iter()
+            .flat_map(|part| &part.music)
+            .any(|(_, duration)| duration.ticks(self.divisions).unwrap_or(0) == 0);
+        if too_short {
+            return None;
+        }
+        let mut out = String::new();
+        self.write(&mut out, measure).ok()?;
+        Some(out)
+    }
+
+    fn write(&self, out: &mut String, measure: u32) -> fmt::Result {
+        writeln!(
+            out,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>"
+        )?;
+        writeln!(
+            out,
+            "<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \
+             \"http://www.musicxml.org/dtds/partwise.dtd\">"
+        )?;
+        writeln!(out, "<score-partwise version=\"4.0\">")?;
+        writeln!(out, "  <part-list>")?;
+        for (i, part) in self.parts.iter().enumerate() {
+            writeln!(out, "    <score-part id=\"P{}\">", i + 1)?;
+            writeln!(out, "      <part-name>{}</part-name>", escape(&part.name))?;
+            writeln!(out, "    </score-part>")?;
+        }
+        writeln!(out, "  </part-list>")?;
+        for (i, part) in self.parts.iter().enumerate() {
+            writeln!(out, "  <part id=\"P{}\">", i + 1)?;
+            self.write_part(out, part, measure)?;
+            writeln!(out, "  </part>")?;
+        }
+        writeln!(out, "</score-partwise>")
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ParseMusicXmlErrorKind {
+    /// The input is not well-formed XML.
+    InvalidXml,
+    /// The document is not a partwise score, e.g. a timewise one.
+    UnsupportedDocument,
+    MissingElement,
+    /// An element or attribute holds text that cannot be read, e.g. a step `H`.
+    InvalidValue,
+    /// A note lasts no plain or dotted note value, e.g. a triplet, or its `<type>` does not
+    /// match its `<duration>`.
+    UnsupportedDuration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMusicXmlError {
+    pub kind: ParseMusicXmlErrorKind,
+    /// Byte range of the offending part of the input.
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseMusicXmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseMusicXmlErrorKind::*;
+        let message = match self.kind {
+            InvalidXml => "invalid XML",
+            UnsupportedDocument => "unsupported document",
+            MissingElement => "missing element",
+            InvalidValue => "invalid value",
+            UnsupportedDuration => "unsupported duration",
+        };
+        write!(f, "{} at {}..{}", message, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseMusicXmlError {}
+
+/// An XML element with its attributes, child elements and text.
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Tag>,
+    text: String,
+    /// Byte range of the start tag.
+    span: Range<usize>,
+    /// Byte range of the content between the start and end tags.
+    content: Range<usize>,
+}
+
+impl Tag {
+    fn child(&self, name: &str) -> Option<&Tag> {
+        self.children.iter().find(|tag| tag.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Tag> {
+        self.children.iter().filter(move |tag| tag.name == name)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn require(&self, name: &str) -> Result<&Tag, ParseMusicXmlError> {
+        self.child(name).ok_or(ParseMusicXmlError {
+            kind: ParseMusicXmlErrorKind::MissingElement,
+            span: self.span.clone(),
+        })
+    }
+
+    fn value<T: FromStr>(&self) -> Result<T, ParseMusicXmlError> {
+        self.text.trim().parse().map_err(|_| ParseMusicXmlError {
+            kind: ParseMusicXmlErrorKind::InvalidValue,
+            span: self.content.clone(),
+        })
+    }
+}
+
+/// Reads the subset of XML that MusicXML files use: elements, attributes, text, character
+/// references and CDATA. Declarations, processing instructions and comments are skipped.
+struct Xml<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Number of elements enclosing the one being read.
+    depth: usize,
+}
+
+/// Deepest nesting of elements read. MusicXML needs far less, and deeper documents would
+/// overflow the stack when read or dropped.
+const MAX_DEPTH: usize = 64;
+
+impl<'a> Xml<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error(&self, start: usize) -> ParseMusicXmlError {
+        ParseMusicXmlError {
+            kind: ParseMusicXmlErrorKind::InvalidXml,
+            span: start..self.pos.max(start + 1),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Moves past the next `end`, or fails if there is none.
+    fn skip_past(&mut self, end: &str, start: usize) -> Result<&'a str, ParseMusicXmlError> {
+        match self.rest().find(end) {
+            Some(i) => {
+                let skipped = &self.rest()[..i];
+                self.pos += i + end.len();
+                Ok(skipped)
+            }
+            None => {
+                self.pos = self.input.len();
+                Err(self.error(start))
+            }
+        }
+    }
+
+    /// Skips a comment, processing instruction or declaration if one starts here.
+    fn skip_markup(&mut self) -> Result<bool, ParseMusicXmlError> {
+        let start = self.pos;
+        if self.rest().starts_with("<!--") {
+            self.skip_past("-->", start)?;
+        } else if self.rest().starts_with("<?") {
+            self.skip_past("?>", start)?;
+        } else if self.rest().starts_with("<!DOCTYPE") {
+            // The internal subset in brackets may contain `>`.
+            let end = self
+                .rest()
+                .find(['[', '>'])
+                .ok_or_else(|| self.error(start))?;
+            self.pos += end;
+            if self.rest().starts_with('[') {
+                self.skip_past("]", start)?;
+            }
+            self.skip_past(">", start)?;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn name(&mut self) -> Result<String, ParseMusicXmlError> {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        match len {
+            0 => Err(self.error(start)),
+            _ => Ok(self.input[start..self.pos].to_string()),
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), ParseMusicXmlError> {
+        if self.rest().starts_with(text) {
+            self.pos += text.len();
+            Ok(())
+        } else {
+            Err(self.error(self.pos))
+        }
+    }
+
+    fn document(&mut self) -> Result<Tag, ParseMusicXmlError> {
+        loop {
+            self.skip_whitespace();
+            if !self.skip_markup()? {
+                break;
+            }
+        }
+        let root = self.tag()?;
+        loop {
+            self.skip_whitespace();
+            if !self.skip_markup()? {
+                break;
+            }
+        }
+        match self.rest().is_empty() {
+            true => Ok(root),
+            false => Err(self.error(self.pos)),
+        }
+    }
+
+    fn tag(&mut self) -> Result<Tag, ParseMusicXmlError> {
+        let start = self.pos;
+        self.expect("<")?;
+        let name = self.name()?;
+        let mut attributes = vec![];
+        let closed = loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                break true;
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break false;
+            }
+            let key = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return Err(self.error(self.pos)),
+            };
+            self.pos += 1;
+            let value_start = self.pos;
+            let value = self.skip_past(&quote.to_string(), start)?;
+            attributes.push((key, unescape(value, value_start)?));
+        };
+
+        let mut tag = Tag {
+            name,
+            attributes,
+            children: vec![],
+            text: String::new(),
+            span: start..self.pos,
+            content: self.pos..self.pos,
+        };
+        if closed {
+            return Ok(tag);
+        }
+
+        loop {
+            if self.rest().is_empty() {
+                return Err(self.error(start));
+            } else if self.rest().starts_with("</") {
+                tag.content.end = self.pos;
+                let end = self.pos;
+                self.pos += 2;
+                if self.name()? != tag.name {
+                    return Err(self.error(end));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(tag);
+            } else if self.rest().starts_with("<![CDATA[") {
+                let cdata = self.pos;
+                self.pos += "<![CDATA[".len();
+                tag.text += self.skip_past("]]>", cdata)?;
+            } else if self.skip_markup()? {
+                continue;
+            } else if self.rest().starts_with('<') {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error(self.pos));
+                }
+                self.depth += 1;
+                tag.children.push(self.tag()?);
+                self.depth -= 1;
+            } else {
+                let text_start = self.pos;
+                let len = self.rest().find('<').unwrap_or(self.rest().len());
+                self.pos += len;
+                tag.text += &unescape(&self.input[text_start..self.pos], text_start)?;
+            }
+        }
+    }
+}
+
+/// Replaces entity and character references in text that starts at byte `start`.
+fn unescape(text: &str, start: usize) -> Result<String, ParseMusicXmlError> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        result += &rest[..i];
+        let offset = start + text.len() - rest.len() + i;
+        let error = ParseMusicXmlError {
+            kind: ParseMusicXmlErrorKind::InvalidXml,
+            span: offset..offset + 1,
+        };
+        let end = rest[i..].find(';').ok_or(error.clone())? + i;
+        let reference = &rest[i + 1..end];
+        let c = match reference {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => reference.strip_prefix('#').and_then(|d| d.parse().ok()),
+                };
+                code.and_then(char::from_u32).ok_or(ParseMusicXmlError {
+                    span: offset..start + text.len() - rest.len() + end + 1,
+                    ..error
+                })?
+            }
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+    result += rest;
+    Ok(result)
+}
+
+/// An element read from a part before ties are joined.
+struct Read {
+    element: Element,
+    ticks: u32,
+    duration: Duration,
+    tie_start: bool,
+    tie_stop: bool,
+    divisions: u32,
+}
+
+fn note_name(tag: &Tag) -> Result<NoteName, ParseMusicXmlError> {
+    match tag.text.trim() {
+        "C" => Ok(NoteName::C),
+        "D" => Ok(NoteName::D),
+        "E" => Ok(NoteName::E),
+        "F" => Ok(NoteName::F),
+        "G" => Ok(NoteName::G),
+        "A" => Ok(NoteName::A),
+        "B" => Ok(NoteName::B),
+        _ => Err(ParseMusicXmlError {
+            kind: ParseMusicXmlErrorKind::InvalidValue,
+            span: tag.content.clone(),
+        }),
+    }
+}
+
+impl Score {
+    /// Reads the attributes of a measure into the current divisions of the part, and the
+    /// key and time signature into the score if `signatures` is set.
+    fn read_attributes(
+        &mut self,
+        attributes: &Tag,
+        divisions: &mut Option<u32>,
+        signatures: bool,
+    ) -> Result<(), ParseMusicXmlError> {
+        if let Some(tag) = attributes.child("divisions") {
+            let value: u32 = tag.value()?;
+            // Dotted whole notes must still count in `u32` ticks.
+            if value == 0 || value > u32::MAX / 8 {
+                return Err(ParseMusicXmlError {
+                    kind: ParseMusicXmlErrorKind::InvalidValue,
+                    span: tag.content.clone(),
+                });
+            }
+            *divisions = Some(value);
+        }
+        if !signatures {
+            return Ok(());
+        }
+        if let Some(divisions) = divisions {
+            self.divisions = *divisions;
+        }
+        if let Some(key) = attributes.child("key") {
+            if let Some(fifths) = key.child("fifths") {
+                self.key = Some(KeySignature::Standard(fifths.value()?));
+                self.minor = key.child("mode").is_some_and(|m| m.text.trim() == "minor");
+            } else {
+                let mut accidentals = BTreeMap::new();
+                let steps = key.children("key-step");
+                for (step, alter) in steps.zip(key.children("key-alter")) {
+                    let alter: Accidentals = alter.value()?;
+                    if alter != 0 {
+                        accidentals.insert(note_name(step)?, alter);
+                    }
+                }
+                self.key = Some(KeySignature::Custom(accidentals));
+            }
+        }
+        if let Some(time) = attributes.child("time") {
+            // Composite signatures such as 3+2/8 are not kept.
+            if let (Some(beats), Some(beat_type)) = (time.child("beats"), time.child("beat-type")) {
+                if let (Ok(count), Ok(value)) = (beats.value::<u8>(), beat_type.value::<u8>()) {
+                    let invalid = |tag: &Tag| ParseMusicXmlError {
+                        kind: ParseMusicXmlErrorKind::InvalidValue,
+                        span: tag.content.clone(),
+                    };
+                    if count == 0 {
+                        return Err(invalid(beats));
+                    }
+                    if !value.is_power_of_two() {
+                        return Err(invalid(beat_type));
+                    }
+                    self.time = (count, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the notes, chords and rests of the first voice of a part, joining tied
+    /// elements where their total is a note value.
+    fn read_part(
+        &mut self,
+        part: &Tag,
+        signatures: bool,
+    ) -> Result<Vec<(Element, Duration)>, ParseMusicXmlError> {
+        let mut divisions = None;
+        let mut voice = None;
+        let mut read: Vec<Read> = vec![];
+
+        for (number, measure) in part.children("measure").enumerate() {
+            for tag in &measure.children {
+                match tag.name.as_str() {
+                    "attributes" => {
+                        self.read_attributes(tag, &mut divisions, signatures && number == 0)?
+                    }
+                    "note" if tag.child("grace").is_none() && tag.child("cue").is_none() => {
+                        let note_voice = tag.child("voice").map(|v| v.text.trim().to_string());
+                        if *voice.get_or_insert(note_voice.clone()) != note_voice {
+                            continue;
+                        }
+                        if let Some(element) = read_note(tag, divisions, &mut read)? {
+                            read.push(element);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut music: Vec<Read> = vec![];
+        for next in read {
+            if let Some(last) = music.last_mut() {
+                let joined = (last.tie_start && next.tie_stop && last.element == next.element)
+                    .then(|| {
+                        Duration::from_ticks(last.ticks.checked_add(next.ticks)?, last.divisions)
+                    })
+                    .flatten()
+                    .filter(|_| last.divisions == next.divisions);
+                if let Some(duration) = joined {
+                    last.ticks += next.ticks;
+                    last.duration = duration;
+                    last.tie_start = next.tie_start;
+                    continue;
+                }
+            }
+            music.push(next);
+        }
+        Ok(music
+            .into_iter()
+            .map(|read| (read.element, read.duration))
+            .collect())
+    }
+}
+
+/// Reads a `<note>`. Notes marked `<chord/>` are added to the previous element instead of
+/// being returned.
+fn read_note(
+    tag: &Tag,
+    divisions: Option<u32>,
+    read: &mut [Read],
+) -> Result<Option<Read>, ParseMusicXmlError> {
+    // Percussion notes have no pitch and are kept as rests so the timing is preserved.
+    let note = match tag.child("pitch") {
+        Some(pitch) => Some(Note {
+            pitch: Pitch {
+                name: note_name(pitch.require("step")?)?,
+                accidentals: match pitch.child("alter") {
+                    Some(alter) => alter.value()?,
+                    None => 0,
+                },
+            },
+            octave: pitch.require("octave")?.value()?,
+        }),
+        None => None,
+    };
+    let tie = |kind: &str| {
+        tag.children("tie")
+            .any(|t| t.attribute("type") == Some(kind))
+    };
+    let (tie_start, tie_stop) = (tie("start"), tie("stop"));
+
+    if let (Some(_), Some(last), Some(note)) = (tag.child("chord"), read.last_mut(), note) {
+        match &mut last.element {
+            Element::Note(first) => last.element = Element::Chord(Notes(vec![*first, note])),
+            Element::Chord(notes) => notes.0.push(note),
+            Element::Rest => {}
+        }
+        last.tie_start &= tie_start;
+        last.tie_stop &= tie_stop;
+        return Ok(None);
+    }
+
+    let ticks = match tag.child("duration") {
+        Some(duration) => duration.value()?,
+        None => 0,
+    };
+    let unsupported = |span: &Range<usize>| ParseMusicXmlError {
+        kind: ParseMusicXmlErrorKind::UnsupportedDuration,
+        span: span.clone(),
+    };
+    if let Some(modification) = tag.child("time-modification") {
+        return Err(unsupported(&modification.span));
+    }
+    // Whole-measure rests may be typed as whole notes in any time signature.
+    let measure_rest = tag
+        .child("rest")
+        .is_some_and(|rest| rest.attribute("measure") == Some("yes"));
+    let duration = match tag.child("type").filter(|_| !measure_rest) {
+        Some(kind) => Duration {
+            value: match kind.text.trim() {
+                "whole" => 1,
+                "half" => 2,
+                "quarter" => 4,
+                "eighth" => 8,
+                "16th" => 16,
+                "32nd" => 32,
+                "64th" => 64,
+                "128th" => 128,
+                _ => return Err(unsupported(&kind.content)),
+            },
+            dots: tag.children("dot").count() as u32,
+        },
+        None => divisions
+            .and_then(|divisions| Duration::from_ticks(ticks, divisions))
+            .ok_or_else(|| unsupported(&tag.span))?,
+    };
+    if let (Some(written), Some(divisions)) = (tag.child("duration"), divisions) {
+        if Duration::from_ticks(ticks, divisions) != Some(duration) {
+            return Err(unsupported(&written.content));
+        }
+    }
+
+    Ok(Some(Read {
+        element: note.map_or(Element::Rest, Element::Note),
+        ticks,
+        duration,
+        tie_start,
+        tie_stop,
+        divisions: divisions.unwrap_or(0),
+    }))
+}
+
+impl FromStr for Score {
+    type Err = ParseMusicXmlError;
+
+    /// Parses a partwise MusicXML document, keeping the written spelling of each note.
+    ///
+    /// Each part holds the notes, chords and rests of its first voice; grace and cue notes,
+    /// chord symbols and other voices are left out. The key and time signature are taken
+    /// from the first measure of the first part.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let root = Xml {
+            input: s,
+            pos: 0,
+            depth: 0,
+        }
+        .document()?;
+        if root.name != "score-partwise" {
+            return Err(ParseMusicXmlError {
+                kind: ParseMusicXmlErrorKind::UnsupportedDocument,
+                span: root.span,
+            });
+        }
+
+        let names: Vec<(&str, String)> = root
+            .child("part-list")
+            .into_iter()
+            .flat_map(|list| list.children("score-part"))
+            .filter_map(|part| {
+                let name = part.child("part-name").map(|name| name.text.clone());
+                Some((part.attribute("id")?, name.unwrap_or_default()))
+            })
+            .collect();
+
+        let mut score = Score::default();
+        for (i, part) in root.children("part").enumerate() {
+            let name = names
+                .iter()
+                .find(|(id, _)| Some(*id) == part.attribute("id"))
+                .map(|(_, name)| name.clone())
+                .unwrap_or_default();
+            let music = score.read_part(part, i == 0)?;
+            score.parts.push(Part {
+                name,
+                music,
+                harmony: vec![],
+            });
+        }
+        Ok(score)
+    }
+}
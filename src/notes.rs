@@ -1,6 +1,11 @@
-use std::{collections::HashSet, fmt};
+use std::{cmp::Ordering, collections::HashSet, fmt, ops::Range, str::FromStr};
 
-use crate::intervals::{Diatonic, Interval};
+use crate::{
+    intervals::{Diatonic, DirectedInterval, Direction, Interval},
+    keys::KeySignature,
+    scales::{spell, Scale},
+    tuning::Tuning,
+};
 
 pub type Accidentals = i32;
 pub const NATURAL: i32 = 0;
@@ -11,6 +16,19 @@ pub const DOUBLE_FLAT: i32 = -2;
 
 pub type Octave = u32;
 
+/// How to spell a pitch that is only known by its pitch class, such as a MIDI key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Spelling {
+    Sharps,
+    Flats,
+    /// Uses the spelling of a matching pitch in the set, e.g. the pitches of a spelled scale.
+    /// Other pitches use flats if the accidentals of the set sum to less than zero, and
+    /// sharps otherwise.
+    Key(HashSet<Pitch>),
+    /// Uses the pitches of a key signature, and otherwise its sharps or flats.
+    Signature(KeySignature),
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum NoteName {
     C,
@@ -22,6 +40,27 @@ pub enum NoteName {
     B,
 }
 
+impl NoteName {
+    /// Position of the letter name within the octave, counting from C.
+    pub fn index(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Number of semitones from C up to the natural note with this name.
+    pub fn semitones(&self) -> i32 {
+        use NoteName::*;
+        match *self {
+            C => 0,
+            D => 2,
+            E => 4,
+            F => 5,
+            G => 7,
+            A => 9,
+            B => 11,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Note {
     pub pitch: Pitch,
@@ -35,6 +74,72 @@ pub struct Pitch {
 }
 
 impl Pitch {
+    /// Number of semitones above C within the octave, so enharmonic pitches share a class.
+    pub fn pitch_class(&self) -> i32 {
+        (self.name.semitones() + self.accidentals).rem_euclid(12)
+    }
+
+    /// Spells a pitch class, given as semitones above C, according to `spelling`.
+    pub fn from_pitch_class(class: i32, spelling: &Spelling) -> Pitch {
+        use NoteName::*;
+
+        let class = class.rem_euclid(12);
+        let sharps = || {
+            [
+                (C, 0),
+                (C, 1),
+                (D, 0),
+                (D, 1),
+                (E, 0),
+                (F, 0),
+                (F, 1),
+                (G, 0),
+                (G, 1),
+                (A, 0),
+                (A, 1),
+                (B, 0),
+            ][class as usize]
+        };
+        let flats = || {
+            [
+                (C, 0),
+                (D, -1),
+                (D, 0),
+                (E, -1),
+                (E, 0),
+                (F, 0),
+                (G, -1),
+                (G, 0),
+                (A, -1),
+                (A, 0),
+                (B, -1),
+                (B, 0),
+            ][class as usize]
+        };
+
+        let (name, accidentals) = match spelling {
+            Spelling::Sharps => sharps(),
+            Spelling::Flats => flats(),
+            Spelling::Key(pitches) => {
+                let in_key = pitches
+                    .iter()
+                    .filter(|p| p.pitch_class() == class)
+                    .min_by_key(|p| (p.accidentals.abs(), *p));
+                match in_key {
+                    Some(pitch) => return *pitch,
+                    None if pitches.iter().map(|p| p.accidentals).sum::<i32>() < 0 => flats(),
+                    None => sharps(),
+                }
+            }
+            Spelling::Signature(key) => {
+                let pitches = key.pitches().into_iter().collect();
+                return Pitch::from_pitch_class(class, &Spelling::Key(pitches));
+            }
+        };
+
+        Pitch { name, accidentals }
+    }
+
     pub fn next(&self, semitones: i32) -> Pitch {
         use NoteName::*;
         match self.name {
@@ -115,6 +220,82 @@ impl Pitch {
         pitch.accidentals -= interval.size() as i32;
         pitch
     }
+
+    /// Returns the ascending interval from `self` up to the next `other`, such that
+    /// `self.leap(interval) == other`. Returns `None` if the interval would need more than
+    /// a single diminished or augmented quality.
+    pub fn interval_to(&self, other: &Pitch) -> Option<Interval> {
+        let mut steps = (other.name.index() - self.name.index()).rem_euclid(7);
+        let mut size =
+            other.name.semitones() + other.accidentals - self.name.semitones() - self.accidentals;
+        if other.name.index() < self.name.index() {
+            size += 12;
+        }
+        if steps == 0 && size < 0 {
+            steps = 7;
+            size += 12;
+        }
+        if size < 0 {
+            return None;
+        }
+        Interval::from_steps(steps as u32, size as u32)
+    }
+
+    /// Returns every spelling of this pitch class with at most two accidentals, in letter
+    /// order, e.g. C, D𝄫 and B♯ for C.
+    pub fn enharmonics(&self) -> Vec<Pitch> {
+        use NoteName::*;
+
+        let class = self.pitch_class();
+        [C, D, E, F, G, A, B]
+            .into_iter()
+            .filter_map(|name| {
+                let accidentals = (class - name.semitones() + 6).rem_euclid(12) - 6;
+                (accidentals.abs() <= DOUBLE_SHARP).then_some(Pitch { name, accidentals })
+            })
+            .collect()
+    }
+
+    /// Respells the pitch with at most one accidental. Naturals are preferred, then the
+    /// direction of the original accidentals, so E𝄪 becomes F♯ and C𝄫 becomes B♭.
+    pub fn simplify(&self) -> Pitch {
+        if self.accidentals.abs() <= SHARP {
+            return *self;
+        }
+        let spelling = if self.accidentals < 0 {
+            Spelling::Flats
+        } else {
+            Spelling::Sharps
+        };
+        Pitch::from_pitch_class(self.pitch_class(), &spelling)
+    }
+
+    /// The pitch in Dutch notation, LilyPond's default input language, e.g. `cis` or `ees`.
+    pub fn dutch_name(&self) -> String {
+        let note_name = match self.name {
+            NoteName::C => "c",
+            NoteName::D => "d",
+            NoteName::E => "e",
+            NoteName::F => "f",
+            NoteName::G => "g",
+            NoteName::A => "a",
+            NoteName::B => "b",
+        };
+        note_name.to_string() + &Note::accidental_to_dutch_notation(self.accidentals)
+    }
+
+    /// Respells the pitch to fit `spelling`, e.g. A♯ as B♭ in a key with flats. Use
+    /// `Spelling::from_scale` to respell within a scale.
+    pub fn respell(&self, spelling: &Spelling) -> Pitch {
+        Pitch::from_pitch_class(self.pitch_class(), spelling)
+    }
+}
+
+impl Spelling {
+    /// Spells pitches as they appear in `scale`.
+    pub fn from_scale<S: Scale>(scale: S) -> Spelling {
+        Spelling::Key(pitches(&spell(scale)))
+    }
 }
 
 impl Note {
@@ -189,16 +370,153 @@ impl Note {
         }
     }
 
-    pub fn leap(&self, interval: Interval) -> Note {
-        let mut note = (0..interval.diatonic_steps()).fold(*self, |n, _| n.next(0));
-        note.pitch.accidentals += interval.size() as i32;
-        note
+    /// Panics if the note would fall below octave 0, see `checked_leap`.
+    pub fn leap<I: Into<DirectedInterval>>(&self, interval: I) -> Note {
+        self.checked_leap(interval)
+            .expect("the note falls below octave 0")
+    }
+
+    /// Panics if the note would fall below octave 0, see `checked_fall`.
+    pub fn fall<I: Into<DirectedInterval>>(&self, interval: I) -> Note {
+        self.leap(interval.into().inverted())
+    }
+
+    /// Like `leap`, returning `None` if the note would fall below octave 0.
+    pub fn checked_leap<I: Into<DirectedInterval>>(&self, interval: I) -> Option<Note> {
+        let DirectedInterval {
+            interval,
+            octaves,
+            direction,
+        } = interval.into();
+
+        match direction {
+            Direction::Up => {
+                let mut note = (0..interval.diatonic_steps()).fold(*self, |n, _| n.next(0));
+                note.pitch.accidentals += interval.size() as i32;
+                note.octave += octaves;
+                Some(note)
+            }
+            Direction::Down => {
+                let index = self.pitch.name.index() - interval.diatonic_steps() as i32;
+                let octave = self.octave as i32 + index.div_euclid(7) - octaves as i32;
+                if octave < 0 {
+                    return None;
+                }
+                let mut note = (0..interval.diatonic_steps()).fold(*self, |n, _| n.prev(0));
+                note.pitch.accidentals -= interval.size() as i32;
+                note.octave -= octaves;
+                Some(note)
+            }
+        }
+    }
+
+    /// Like `fall`, returning `None` if the note would fall below octave 0.
+    pub fn checked_fall<I: Into<DirectedInterval>>(&self, interval: I) -> Option<Note> {
+        self.checked_leap(interval.into().inverted())
+    }
+
+    /// Number of semitones above C0.
+    pub fn position(&self) -> i32 {
+        self.octave as i32 * 12 + self.pitch.name.semitones() + self.pitch.accidentals
+    }
+
+    /// Number of letter names above C0, ignoring accidentals.
+    pub fn diatonic_position(&self) -> i32 {
+        self.octave as i32 * 7 + self.pitch.name.index()
+    }
+
+    /// Compares notes by how high they sound. Enharmonic notes are ordered by letter name,
+    /// so B♯3 comes before C4 and C4 before D𝄫4. The derived `Ord` compares by letter name and
+    /// accidentals first instead.
+    pub fn cmp_pitch(&self, other: &Note) -> Ordering {
+        self.position()
+            .cmp(&other.position())
+            .then(self.diatonic_position().cmp(&other.diatonic_position()))
+    }
+
+    /// Returns the MIDI key number of this note, where C4 is 60. Enharmonic notes share a
+    /// number, so B♯3 is 60 as well. Returns `None` outside of the MIDI range.
+    pub fn to_midi(&self) -> Option<u8> {
+        u8::try_from(self.position() + 12)
+            .ok()
+            .filter(|&key| key <= 127)
+    }
+
+    /// Returns the note for a MIDI key number, spelled according to `spelling`. Returns
+    /// `None` for keys below C0, which cannot be represented.
+    pub fn from_midi(key: u8, spelling: &Spelling) -> Option<Note> {
+        Note::from_position(key as i32 - 12, spelling)
+    }
+
+    /// Returns the note `position` semitones above C0, spelled according to `spelling`.
+    /// Returns `None` if the spelled note would fall below octave 0.
+    pub fn from_position(position: i32, spelling: &Spelling) -> Option<Note> {
+        let pitch = Pitch::from_pitch_class(position, spelling);
+        let octave = (position - pitch.name.semitones() - pitch.accidentals).div_euclid(12);
+        Some(Note {
+            pitch,
+            octave: u32::try_from(octave).ok()?,
+        })
+    }
+
+    /// Respells a note with double accidentals using at most one, keeping its sound, so B𝄪3
+    /// becomes C♯4. Notes with a single accidental, such as B♯3, are kept.
+    pub fn simplify(&self) -> Option<Note> {
+        self.respell(&Spelling::Key(HashSet::from([self.pitch.simplify()])))
+    }
+
+    /// Respells the note to fit `spelling`, keeping its sound. Returns `None` if the
+    /// respelled note would fall below octave 0.
+    pub fn respell(&self, spelling: &Spelling) -> Option<Note> {
+        Note::from_position(self.position(), spelling)
     }
 
-    pub fn fall(&self, interval: Interval) -> Note {
-        let mut note = (0..interval.diatonic_steps()).fold(*self, |n, _| n.prev(0));
-        note.pitch.accidentals -= interval.size() as i32;
-        note
+    /// Frequency in Hz in twelve-tone equal temperament with A4 at 440 Hz. Use
+    /// `Tuning::frequency` for other reference pitches.
+    pub fn frequency(&self) -> f64 {
+        Tuning::default().frequency(self)
+    }
+
+    /// Returns the note closest to `hz`, spelled with sharps, and how many cents `hz` lies
+    /// above it, with A4 at 440 Hz. Use `Tuning::nearest` for other reference pitches.
+    pub fn nearest(hz: f64) -> Option<(Note, f64)> {
+        Tuning::default().nearest(hz, &Spelling::Sharps)
+    }
+
+    /// Returns the interval from `self` to `other`, such that
+    /// `self.leap(self.interval_to(&other).unwrap()) == other`. The direction follows the
+    /// letter names, so C4 to B♯3 is a descending diminished second. Returns `None` if the
+    /// interval would need more than a single diminished or augmented quality.
+    pub fn interval_to(&self, other: &Note) -> Option<DirectedInterval> {
+        let mut steps = other.diatonic_position() - self.diatonic_position();
+        let mut size = other.position() - self.position();
+
+        let direction = if steps > 0 || (steps == 0 && size >= 0) {
+            Direction::Up
+        } else {
+            Direction::Down
+        };
+        if direction == Direction::Down {
+            steps = -steps;
+            size = -size;
+        }
+
+        let mut octaves = steps / 7;
+        if steps % 7 == 0 && octaves > 0 {
+            octaves -= 1;
+        }
+        let size = size - 12 * octaves;
+        if size < 0 {
+            return None;
+        }
+
+        Interval::from_steps((steps - 7 * octaves) as u32, size as u32).map(|interval| {
+            DirectedInterval {
+                interval,
+                octaves: octaves as u32,
+                direction,
+            }
+        })
     }
 
     fn accidental_to_string(accidental: Accidentals) -> String {
@@ -250,41 +568,181 @@ impl fmt::Display for Pitch {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseNoteErrorKind {
+    Empty,
+    InvalidNoteName,
+    UnexpectedSymbol,
+    MissingOctave,
+    InvalidOctave,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNoteError {
+    pub kind: ParseNoteErrorKind,
+    /// Byte range of the offending part of the input.
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseNoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseNoteErrorKind::*;
+        let message = match self.kind {
+            Empty => "empty note",
+            InvalidNoteName => "invalid note name",
+            UnexpectedSymbol => "unexpected symbol",
+            MissingOctave => "missing octave",
+            InvalidOctave => "invalid octave",
+        };
+        write!(f, "{} at {}..{}", message, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseNoteError {}
+
+/// A pitch read from the start of a string, followed by whatever came after it.
+struct ParsedPitch<'a> {
+    pitch: Pitch,
+    /// Whether the pitch was written in Dutch (LilyPond) notation, e.g. `cis` or `ees`.
+    dutch: bool,
+    rest: &'a str,
+    pos: usize,
+}
+
+fn parse_pitch(input: &str) -> Result<ParsedPitch<'_>, ParseNoteError> {
+    use ParseNoteErrorKind::*;
+
+    let first = input.chars().next().ok_or(ParseNoteError {
+        kind: Empty,
+        span: 0..0,
+    })?;
+    let name = match first.to_ascii_uppercase() {
+        'C' => NoteName::C,
+        'D' => NoteName::D,
+        'E' => NoteName::E,
+        'F' => NoteName::F,
+        'G' => NoteName::G,
+        'A' => NoteName::A,
+        'B' => NoteName::B,
+        _ => {
+            return Err(ParseNoteError {
+                kind: InvalidNoteName,
+                span: 0..first.len_utf8(),
+            })
+        }
+    };
+
+    let mut rest = &input[1..];
+    let mut accidentals = 0;
+
+    // LilyPond shortens `ees` and `aes` to `es` and `as`.
+    let short_flat = matches!(name, NoteName::E | NoteName::A) && rest.starts_with('s');
+    let dutch = first.is_ascii_lowercase()
+        && (short_flat || rest.starts_with("is") || rest.starts_with("es"));
+
+    if dutch {
+        if short_flat {
+            accidentals -= 1;
+            rest = &rest[1..];
+        }
+        loop {
+            if let Some(r) = rest.strip_prefix("is") {
+                accidentals += 1;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("es") {
+                accidentals -= 1;
+                rest = r;
+            } else {
+                break;
+            }
+        }
+    } else {
+        loop {
+            let accidental = match rest.chars().next() {
+                Some('#' | '♯') => 1,
+                Some('b' | '♭') => -1,
+                Some('x' | '𝄪') => 2,
+                Some('𝄫') => -2,
+                _ => break,
+            };
+            accidentals += accidental;
+            rest = &rest[rest.chars().next().unwrap().len_utf8()..];
+        }
+    }
+
+    Ok(ParsedPitch {
+        pitch: Pitch { name, accidentals },
+        dutch: dutch || first.is_ascii_lowercase(),
+        pos: input.len() - rest.len(),
+        rest,
+    })
+}
+
+impl FromStr for Pitch {
+    type Err = ParseNoteError;
+
+    /// Parses a pitch without octave, such as `C#`, `Db`, `C♯`, `Bbb` or, in Dutch notation,
+    /// `cis` and `eeses`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = parse_pitch(s)?;
+        if !parsed.rest.is_empty() {
+            return Err(ParseNoteError {
+                kind: ParseNoteErrorKind::UnexpectedSymbol,
+                span: parsed.pos..s.len(),
+            });
+        }
+        Ok(parsed.pitch)
+    }
+}
+
+impl FromStr for Note {
+    type Err = ParseNoteError;
+
+    /// Parses a note in scientific pitch notation, such as `C#4`, `Db3`, `C♯4` or `Bbb2`, or
+    /// in LilyPond's absolute Dutch notation, such as `cis'` for C♯4 or `bes,` for B♭2. A
+    /// lowercase note without octave marks lies in octave 3, as in LilyPond.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ParseNoteErrorKind::*;
+
+        let ParsedPitch {
+            pitch,
+            dutch,
+            rest,
+            pos,
+        } = parse_pitch(s)?;
+        let error = |kind| ParseNoteError {
+            kind,
+            span: pos..s.len(),
+        };
+
+        let octave = if !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()) {
+            rest.parse().map_err(|_| error(InvalidOctave))?
+        } else if rest.chars().all(|c| c == '\'' || c == ',') && (dutch || !rest.is_empty()) {
+            let marks: i32 = rest.chars().map(|c| if c == '\'' { 1 } else { -1 }).sum();
+            u32::try_from(3 + marks).map_err(|_| error(InvalidOctave))?
+        } else if rest.is_empty() {
+            return Err(error(MissingOctave));
+        } else {
+            return Err(error(UnexpectedSymbol));
+        };
+
+        Ok(Note { pitch, octave })
+    }
+}
+
 pub trait FormatAsCode {
     fn fmt_as_code(&self) -> String;
 }
 
 impl FormatAsCode for Note {
     fn fmt_as_code(&self) -> String {
-        let note_name = match self.pitch.name {
-            NoteName::C => "c",
-            NoteName::D => "d",
-            NoteName::E => "e",
-            NoteName::F => "f",
-            NoteName::G => "g",
-            NoteName::A => "a",
-            NoteName::B => "b",
-        };
-
-        let accidental_str = Note::accidental_to_dutch_notation(self.pitch.accidentals);
-        format!("{}{}!({})", note_name, accidental_str, self.octave)
+        format!("{}!({})", self.pitch.dutch_name(), self.octave)
     }
 }
 
 impl FormatAsCode for Pitch {
     fn fmt_as_code(&self) -> String {
-        let note_name = match self.name {
-            NoteName::C => "c",
-            NoteName::D => "d",
-            NoteName::E => "e",
-            NoteName::F => "f",
-            NoteName::G => "g",
-            NoteName::A => "a",
-            NoteName::B => "b",
-        };
-
-        let accidental_str = Note::accidental_to_dutch_notation(self.accidentals);
-        format!("{}{}!()", note_name, accidental_str)
+        format!("{}!()", self.dutch_name())
     }
 }
 
@@ -317,6 +775,28 @@ impl FormatAsCode for Notes {
     }
 }
 
+impl Notes {
+    /// Sorts the notes from low to high as they sound, see `Note::cmp_pitch`.
+    pub fn sort_by_pitch(&mut self) {
+        self.0.sort_by(Note::cmp_pitch);
+    }
+
+    pub fn lowest(&self) -> Option<Note> {
+        self.0.iter().copied().min_by(Note::cmp_pitch)
+    }
+
+    pub fn highest(&self) -> Option<Note> {
+        self.0.iter().copied().max_by(Note::cmp_pitch)
+    }
+
+    /// Whether every note sounds between `low` and `high`, inclusive.
+    pub fn within(&self, low: Note, high: Note) -> bool {
+        self.0
+            .iter()
+            .all(|n| n.position() >= low.position() && n.position() <= high.position())
+    }
+}
+
 impl IntoIterator for Notes {
     type Item = Note;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -0,0 +1,385 @@
+use std::{fmt, ops::Range, str::FromStr};
+
+use crate::{
+    chords::{Alterations, Chord},
+    intervals::{AnyInterval, ImperfectInterval, Interval},
+    notes::{Accidentals, Note},
+    scales::{Qualities, Scale, Tensions},
+};
+
+const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+/// The seventh on top of a triad in Roman numeral analysis.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Sevenths {
+    Minor,
+    Major,
+    Diminished,
+}
+
+/// A chord named by the scale degree of its root, e.g. `V⁶₅` or `♭VII`. Only triads and
+/// seventh chords are named; the quality is one of major, minor, diminished or augmented.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RomanNumeral {
+    degree: usize,
+    accidentals: Accidentals,
+    quality: Qualities,
+    seventh: Option<Sevenths>,
+    inversion: usize,
+}
+
+impl RomanNumeral {
+    /// Builds a numeral on scale degree `degree`, from 1 to 7, whose root carries
+    /// `accidentals` relative to the scale, as in `♭VII`. `inversion` is 0 for root position,
+    /// 1 for the third in the bass and so on. Returns `None` for other degrees, for
+    /// inversions the chord does not have, or for a quality other than major, minor,
+    /// diminished or augmented.
+    pub fn new(
+        degree: usize,
+        accidentals: Accidentals,
+        quality: Qualities,
+        seventh: Option<Sevenths>,
+        inversion: usize,
+    ) -> Option<RomanNumeral> {
+        let triad = matches!(
+            quality,
+            Qualities::Major | Qualities::Minor | Qualities::Diminshed | Qualities::Augmented
+        );
+        let inversions = if seventh.is_some() { 3 } else { 2 };
+        (triad && (1..=7).contains(&degree) && inversion <= inversions).then_some(RomanNumeral {
+            degree,
+            accidentals,
+            quality,
+            seventh,
+            inversion,
+        })
+    }
+
+    /// Scale degree of the root, from 1 to 7.
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Accidentals the root carries relative to the scale.
+    pub fn accidentals(&self) -> Accidentals {
+        self.accidentals
+    }
+
+    pub fn quality(&self) -> Qualities {
+        self.quality
+    }
+
+    pub fn seventh(&self) -> Option<Sevenths> {
+        self.seventh
+    }
+
+    /// 0 for root position, 1 for the third in the bass and so on.
+    pub fn inversion(&self) -> usize {
+        self.inversion
+    }
+
+    /// Names `chord` relative to the scale `key`, e.g. B♭ major as `♭VII` in C major.
+    /// Tensions other than the seventh are ignored. Returns `None` for chords without a plain
+    /// triad, such as suspended chords, or with a bass that is not a chord tone.
+    pub fn analyze<S: Scale>(key: S, chord: &Chord) -> Option<RomanNumeral> {
+        let steps = chord.root.pitch.name.index() - key.root().pitch.name.index();
+        let degree = steps.rem_euclid(7) as usize + 1;
+        let diatonic = key.degree(degree)?.pitch;
+        if diatonic.name != chord.root.pitch.name {
+            return None;
+        }
+
+        let altered = |alteration| chord.alterations.contains(&alteration);
+        let quality = match chord.quality {
+            Qualities::Major if altered(Alterations::FlatFifth) => return None,
+            Qualities::Major if altered(Alterations::SharpFifth) => Qualities::Augmented,
+            Qualities::Minor if altered(Alterations::SharpFifth) => return None,
+            Qualities::Minor if altered(Alterations::FlatFifth) => Qualities::Diminshed,
+            Qualities::Sus4 | Qualities::Sus2 => return None,
+            quality => quality,
+        };
+        let seventh = if chord.tensions.contains(&Tensions::MajorSeventh) {
+            Some(Sevenths::Major)
+        } else if chord.tensions.contains(&Tensions::Seventh) {
+            Some(match chord.quality {
+                Qualities::Diminshed => Sevenths::Diminished,
+                _ => Sevenths::Minor,
+            })
+        } else {
+            None
+        };
+
+        let mut numeral = RomanNumeral {
+            degree,
+            accidentals: chord.root.pitch.accidentals - diatonic.accidentals,
+            quality,
+            seventh,
+            inversion: 0,
+        };
+        if let Some(bass) = chord.bass {
+            let tones: Vec<_> = numeral
+                .intervals()
+                .iter()
+                .map(|&i| chord.root.pitch.leap(i))
+                .collect();
+            numeral.inversion = tones.iter().position(|&p| p == bass).or_else(|| {
+                tones
+                    .iter()
+                    .position(|p| p.pitch_class() == bass.pitch_class())
+            })?;
+        }
+        Some(numeral)
+    }
+
+    /// Builds the chord this numeral stands for in the scale `key`, with the root in the
+    /// octave of the scale degree. Inversions become slash chords, e.g. `V⁶` in C major is
+    /// G/B. Returns `None` if the key has no such degree.
+    pub fn realize<S: Scale>(&self, key: S) -> Option<Chord> {
+        let mut root: Note = key.degree(self.degree)?;
+        root.pitch.accidentals += self.accidentals;
+
+        let mut chord = match (self.quality, self.seventh) {
+            (Qualities::Diminshed, Some(Sevenths::Minor)) => {
+                let mut chord = Chord::new(root, Qualities::Minor);
+                chord.alterations.push(Alterations::FlatFifth);
+                chord
+            }
+            (quality, _) => Chord::new(root, quality),
+        };
+        match self.seventh {
+            Some(Sevenths::Major) => chord.tensions.push(Tensions::MajorSeventh),
+            Some(_) => chord.tensions.push(Tensions::Seventh),
+            None => {}
+        }
+        if self.inversion > 0 {
+            chord.bass = self
+                .intervals()
+                .get(self.inversion)
+                .map(|&i| root.pitch.leap(i));
+        }
+        Some(chord)
+    }
+
+    /// Root, third, fifth and seventh above the root.
+    fn intervals(&self) -> Vec<Interval> {
+        let mut intervals = self.quality.intervals();
+        intervals.extend(self.seventh.map(|seventh| match seventh {
+            Sevenths::Minor => Interval::Minor(ImperfectInterval::Seventh),
+            Sevenths::Major => Interval::Major(ImperfectInterval::Seventh),
+            Sevenths::Diminished => {
+                Interval::Diminshed(AnyInterval::Imperfect(ImperfectInterval::Seventh))
+            }
+        }));
+        intervals
+    }
+
+    fn figure(&self) -> &'static str {
+        match (self.seventh.is_some(), self.inversion) {
+            (false, 0) => "",
+            (false, 1) => "⁶",
+            (false, _) => "⁶₄",
+            (true, 0) => "7",
+            (true, 1) => "⁶₅",
+            (true, 2) => "⁴₃",
+            (true, _) => "⁴₂",
+        }
+    }
+}
+
+/// Upper case for major and augmented chords, lower case for minor and diminished ones,
+/// followed by `°`, `ø` or `+` and the figured bass, e.g. `♭VII`, `V⁶₅`, `vii°7` or `iiø⁴₃`.
+/// Major sevenths are marked with `maj`, as in `Imaj7`.
+impl fmt::Display for RomanNumeral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let accidental = if self.accidentals < 0 { "♭" } else { "♯" };
+        write!(
+            f,
+            "{}",
+            accidental.repeat(self.accidentals.unsigned_abs() as usize)
+        )?;
+
+        let numeral = NUMERALS[(self.degree - 1) % 7];
+        match self.quality {
+            Qualities::Major | Qualities::Augmented => write!(f, "{}", numeral)?,
+            _ => write!(f, "{}", numeral.to_lowercase())?,
+        }
+        match (self.quality, self.seventh) {
+            (Qualities::Diminshed, Some(Sevenths::Minor)) => write!(f, "ø")?,
+            (Qualities::Diminshed, _) => write!(f, "°")?,
+            (Qualities::Augmented, _) => write!(f, "+")?,
+            _ => {}
+        }
+        if self.seventh == Some(Sevenths::Major) {
+            write!(f, "maj")?;
+        }
+        write!(f, "{}", self.figure())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseRomanNumeralErrorKind {
+    Empty,
+    InvalidNumeral,
+    InvalidFigure,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRomanNumeralError {
+    pub kind: ParseRomanNumeralErrorKind,
+    /// Byte range of the offending part of the input.
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseRomanNumeralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseRomanNumeralErrorKind::*;
+        let message = match self.kind {
+            Empty => "empty Roman numeral",
+            InvalidNumeral => "invalid Roman numeral",
+            InvalidFigure => "invalid figured bass",
+        };
+        write!(f, "{} at {}..{}", message, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseRomanNumeralError {}
+
+impl FromStr for RomanNumeral {
+    type Err = ParseRomanNumeralError;
+
+    /// Parses a Roman numeral such as `bVII`, `V65`, `vii°7`, `iiø7` or `IV+`. Figures may
+    /// be written as plain digits or with super- and subscripts, and `o`, `0` and `+` may
+    /// stand in for `°`, `ø` and augmented.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ParseRomanNumeralErrorKind::*;
+
+        if s.is_empty() {
+            return Err(ParseRomanNumeralError {
+                kind: Empty,
+                span: 0..0,
+            });
+        }
+
+        let rest = s.trim_start_matches(['b', '♭', '#', '♯']);
+        let accidentals: Accidentals = s[..s.len() - rest.len()]
+            .chars()
+            .map(|c| if c == '#' || c == '♯' { 1 } else { -1 })
+            .sum();
+        let start = s.len() - rest.len();
+
+        let numeral_len = rest
+            .find(|c: char| !matches!(c.to_ascii_uppercase(), 'I' | 'V'))
+            .unwrap_or(rest.len());
+        let numeral = &rest[..numeral_len];
+        let upper = numeral.to_ascii_uppercase();
+        let degree = NUMERALS
+            .iter()
+            .position(|n| *n == upper)
+            .filter(|_| numeral == upper || numeral == upper.to_lowercase())
+            .ok_or(ParseRomanNumeralError {
+                kind: InvalidNumeral,
+                span: start..start + numeral_len.max(rest.chars().next().map_or(0, char::len_utf8)),
+            })?;
+        let mut quality = if numeral == upper {
+            Qualities::Major
+        } else {
+            Qualities::Minor
+        };
+
+        let mut rest = &rest[numeral_len..];
+        let mut half_diminished = false;
+        if let Some(r) = rest.strip_prefix(['°', 'o']) {
+            quality = Qualities::Diminshed;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix(['ø', '0']) {
+            quality = Qualities::Diminshed;
+            half_diminished = true;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('+') {
+            quality = Qualities::Augmented;
+            rest = r;
+        }
+        let major_seventh = match rest.strip_prefix("maj") {
+            Some(r) => {
+                rest = r;
+                true
+            }
+            None => false,
+        };
+
+        let figure_start = s.len() - rest.len();
+        let figure: String = rest
+            .chars()
+            .map(|c| match c {
+                '²' | '₂' => '2',
+                '³' | '₃' => '3',
+                '⁴' | '₄' => '4',
+                '⁵' | '₅' => '5',
+                '⁶' | '₆' => '6',
+                '⁷' | '₇' => '7',
+                c => c,
+            })
+            .collect();
+        let (seventh, inversion) = match figure.as_str() {
+            "" if half_diminished || major_seventh => (true, 0),
+            "" => (false, 0),
+            "6" => (false, 1),
+            "64" => (false, 2),
+            "7" => (true, 0),
+            "65" => (true, 1),
+            "43" => (true, 2),
+            "42" | "2" => (true, 3),
+            _ => {
+                return Err(ParseRomanNumeralError {
+                    kind: InvalidFigure,
+                    span: figure_start..s.len(),
+                })
+            }
+        };
+
+        let seventh = seventh.then_some(match quality {
+            _ if major_seventh => Sevenths::Major,
+            Qualities::Diminshed if !half_diminished => Sevenths::Diminished,
+            _ => Sevenths::Minor,
+        });
+
+        Ok(RomanNumeral {
+            degree: degree + 1,
+            accidentals,
+            quality,
+            seventh,
+            inversion,
+        })
+    }
+}
+
+/// Parses a progression such as `ii–V–I` or `I vi ii V7`. Numerals may be separated by
+/// dashes or whitespace. Error spans refer to the whole input.
+pub fn parse_progression(progression: &str) -> Result<Vec<RomanNumeral>, ParseRomanNumeralError> {
+    let is_separator = |c: char| c.is_whitespace() || matches!(c, '-' | '–' | '—');
+
+    let mut numerals = vec![];
+    let mut pos = 0;
+    for token in progression.split(is_separator) {
+        if !token.is_empty() {
+            let numeral =
+                token
+                    .parse()
+                    .map_err(|e: ParseRomanNumeralError| ParseRomanNumeralError {
+                        span: e.span.start + pos..e.span.end + pos,
+                        ..e
+                    })?;
+            numerals.push(numeral);
+        }
+        pos += token.len();
+        pos += progression[pos..].chars().next().map_or(0, char::len_utf8);
+    }
+
+    if numerals.is_empty() {
+        return Err(ParseRomanNumeralError {
+            kind: ParseRomanNumeralErrorKind::Empty,
+            span: 0..progression.len(),
+        });
+    }
+    Ok(numerals)
+}
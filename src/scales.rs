@@ -1,6 +1,8 @@
+use std::{fmt, ops::Range};
+
 use crate::{
-    intervals::{AnyInterval, ImperfectInterval, Interval, PerfectInterval},
-    notes::{Note, Notes},
+    intervals::{AnyInterval, Diatonic, ImperfectInterval, Interval, PerfectInterval},
+    notes::{Note, NoteName, Notes},
 };
 
 use ImperfectInterval::*;
@@ -8,15 +10,79 @@ use Interval::*;
 use PerfectInterval::*;
 
 pub trait Scale {
-    fn intervals() -> Vec<Interval>;
+    fn intervals(&self) -> Vec<Interval>;
     fn root(&self) -> Note;
+
+    /// Returns the `n`-th degree of the scale, counting from 1 for the root. Degrees past the
+    /// last note continue in the octaves above, so `degree(8)` of a seven-note scale is the
+    /// root an octave up. Returns `None` for `n` 0 or a scale without notes.
+    fn degree(&self, n: usize) -> Option<Note> {
+        let intervals = sorted(&self.intervals());
+        let index = n.checked_sub(1)?;
+        let mut note = self
+            .root()
+            .leap(*intervals.get(index % intervals.len().max(1))?);
+        note.octave += (index / intervals.len()) as u32;
+        Some(note)
+    }
+
+    /// Moves `note` up or down by `steps` notes of the scale, e.g. up a third in the key for
+    /// `steps == 2`. The note may be in any octave and is matched enharmonically if its
+    /// spelling is not in the scale. Returns `None` if the note does not occur in the scale
+    /// or the result would fall below octave 0.
+    fn step(&self, note: Note, steps: i32) -> Option<Note> {
+        let intervals = sorted(&self.intervals());
+        let spelled: Vec<Note> = intervals.iter().map(|&i| self.root().leap(i)).collect();
+
+        let index = spelled
+            .iter()
+            .position(|n| n.pitch == note.pitch)
+            .or_else(|| {
+                spelled
+                    .iter()
+                    .position(|n| n.pitch.pitch_class() == note.pitch.pitch_class())
+            })?;
+        let octaves = (note.position() - spelled[index].position()).div_euclid(12);
+
+        let target = index as i32 + steps;
+        let len = intervals.len() as i32;
+        let mut result = spelled[target.rem_euclid(len) as usize];
+        result.octave =
+            u32::try_from(result.octave as i32 + octaves + target.div_euclid(len)).ok()?;
+        Some(result)
+    }
 }
 
+impl<S: Scale + ?Sized> Scale for Box<S> {
+    fn intervals(&self) -> Vec<Interval> {
+        (**self).intervals()
+    }
+
+    fn root(&self) -> Note {
+        (**self).root()
+    }
+}
+
+impl<S: Scale + ?Sized> Scale for &S {
+    fn intervals(&self) -> Vec<Interval> {
+        (**self).intervals()
+    }
+
+    fn root(&self) -> Note {
+        (**self).root()
+    }
+}
+
+/// Spells the scale upwards from its root. Every note is spelled by the letter name of its
+/// own interval, so the interval list decides the spelling: seven-note scales use every
+/// letter once, scales with fewer notes skip letters (C major pentatonic has no F or B) and
+/// scales with more notes repeat some (C blues has both F and F♯). Notes are ordered by
+/// size, with the lower letter first for enharmonic intervals.
 pub fn spell<S: Scale>(scale: S) -> Notes {
     let mut notes = vec![];
 
-    let mut intervals: Vec<Interval> = S::intervals();
-    intervals.sort_by_key(|a| a.size());
+    let mut intervals: Vec<Interval> = scale.intervals();
+    intervals.sort_by_key(|a| (a.size(), a.diatonic_steps()));
 
     for interval in intervals {
         notes.push(scale.root().leap(interval));
@@ -25,18 +91,21 @@ pub fn spell<S: Scale>(scale: S) -> Notes {
     Notes(notes)
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Qualities {
     Major,
     Minor,
     Diminshed,
+    Augmented,
     Sus4,
     Sus2,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Tensions {
+    Sixth,
     Seventh,
+    MajorSeventh,
     Ninth,
     Eleventh,
     Thirteenth,
@@ -54,7 +123,7 @@ pub struct Lydian {
 }
 
 impl Scale for Lydian {
-    fn intervals() -> Vec<Interval> {
+    fn intervals(&self) -> Vec<Interval> {
         vec![
             Perfect(Unison),
             Major(Second),
@@ -78,9 +147,9 @@ pub struct Ionian {
 pub type Major = Ionian;
 
 impl Scale for Ionian {
-    fn intervals() -> Vec<Interval> {
+    fn intervals(&self) -> Vec<Interval> {
         replace_in_scale(
-            Lydian::intervals(),
+            Lydian { root: self.root }.intervals(),
             Augmented(AnyInterval::Perfect(Fourth)),
             Perfect(Fourth),
         )
@@ -96,8 +165,12 @@ pub struct Mixolydian {
 }
 
 impl Scale for Mixolydian {
-    fn intervals() -> Vec<Interval> {
-        replace_in_scale(Ionian::intervals(), Major(Seventh), Minor(Seventh))
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Ionian { root: self.root }.intervals(),
+            Major(Seventh),
+            Minor(Seventh),
+        )
     }
 
     fn root(&self) -> Note {
@@ -110,8 +183,12 @@ pub struct Dorian {
 }
 
 impl Scale for Dorian {
-    fn intervals() -> Vec<Interval> {
-        replace_in_scale(Mixolydian::intervals(), Major(Third), Minor(Third))
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Mixolydian { root: self.root }.intervals(),
+            Major(Third),
+            Minor(Third),
+        )
     }
 
     fn root(&self) -> Note {
@@ -126,8 +203,12 @@ pub struct Aeolian {
 pub type Minor = Aeolian;
 
 impl Scale for Aeolian {
-    fn intervals() -> Vec<Interval> {
-        replace_in_scale(Dorian::intervals(), Major(Sixth), Minor(Sixth))
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Dorian { root: self.root }.intervals(),
+            Major(Sixth),
+            Minor(Sixth),
+        )
     }
 
     fn root(&self) -> Note {
@@ -140,8 +221,12 @@ pub struct Phrygian {
 }
 
 impl Scale for Phrygian {
-    fn intervals() -> Vec<Interval> {
-        replace_in_scale(Aeolian::intervals(), Major(Second), Minor(Second))
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Aeolian { root: self.root }.intervals(),
+            Major(Second),
+            Minor(Second),
+        )
     }
 
     fn root(&self) -> Note {
@@ -154,9 +239,9 @@ pub struct Locrian {
 }
 
 impl Scale for Locrian {
-    fn intervals() -> Vec<Interval> {
+    fn intervals(&self) -> Vec<Interval> {
         replace_in_scale(
-            Phrygian::intervals(),
+            Phrygian { root: self.root }.intervals(),
             Perfect(Fifth),
             Diminshed(AnyInterval::Perfect(Fifth)),
         )
@@ -166,3 +251,714 @@ impl Scale for Locrian {
         self.root
     }
 }
+
+pub struct HarmonicMinor {
+    pub root: Note,
+}
+
+impl Scale for HarmonicMinor {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Aeolian { root: self.root }.intervals(),
+            Minor(Seventh),
+            Major(Seventh),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct LocrianNatural6 {
+    pub root: Note,
+}
+
+impl Scale for LocrianNatural6 {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Locrian { root: self.root }.intervals(),
+            Minor(Sixth),
+            Major(Sixth),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct IonianAugmented {
+    pub root: Note,
+}
+
+impl Scale for IonianAugmented {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Ionian { root: self.root }.intervals(),
+            Perfect(Fifth),
+            Augmented(AnyInterval::Perfect(Fifth)),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct DorianSharp4 {
+    pub root: Note,
+}
+
+pub type UkrainianDorian = DorianSharp4;
+
+impl Scale for DorianSharp4 {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Dorian { root: self.root }.intervals(),
+            Perfect(Fourth),
+            Augmented(AnyInterval::Perfect(Fourth)),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct PhrygianDominant {
+    pub root: Note,
+}
+
+impl Scale for PhrygianDominant {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Phrygian { root: self.root }.intervals(),
+            Minor(Third),
+            Major(Third),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct LydianSharp2 {
+    pub root: Note,
+}
+
+impl Scale for LydianSharp2 {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Lydian { root: self.root }.intervals(),
+            Major(Second),
+            Augmented(AnyInterval::Imperfect(Second)),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct SuperLocrianDoubleFlat7 {
+    pub root: Note,
+}
+
+pub type AlteredDiminished = SuperLocrianDoubleFlat7;
+
+impl Scale for SuperLocrianDoubleFlat7 {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Altered { root: self.root }.intervals(),
+            Minor(Seventh),
+            Diminshed(AnyInterval::Imperfect(Seventh)),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct MelodicMinor {
+    pub root: Note,
+}
+
+impl Scale for MelodicMinor {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Dorian { root: self.root }.intervals(),
+            Minor(Seventh),
+            Major(Seventh),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct DorianFlat2 {
+    pub root: Note,
+}
+
+pub type PhrygianNatural6 = DorianFlat2;
+
+impl Scale for DorianFlat2 {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Dorian { root: self.root }.intervals(),
+            Major(Second),
+            Minor(Second),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct LydianAugmented {
+    pub root: Note,
+}
+
+impl Scale for LydianAugmented {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Lydian { root: self.root }.intervals(),
+            Perfect(Fifth),
+            Augmented(AnyInterval::Perfect(Fifth)),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct LydianDominant {
+    pub root: Note,
+}
+
+impl Scale for LydianDominant {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Lydian { root: self.root }.intervals(),
+            Major(Seventh),
+            Minor(Seventh),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct MixolydianFlat6 {
+    pub root: Note,
+}
+
+pub type AeolianDominant = MixolydianFlat6;
+
+impl Scale for MixolydianFlat6 {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Mixolydian { root: self.root }.intervals(),
+            Major(Sixth),
+            Minor(Sixth),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct LocrianNatural2 {
+    pub root: Note,
+}
+
+impl Scale for LocrianNatural2 {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Locrian { root: self.root }.intervals(),
+            Minor(Second),
+            Major(Second),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct Altered {
+    pub root: Note,
+}
+
+pub type SuperLocrian = Altered;
+
+impl Scale for Altered {
+    fn intervals(&self) -> Vec<Interval> {
+        replace_in_scale(
+            Locrian { root: self.root }.intervals(),
+            Perfect(Fourth),
+            Diminshed(AnyInterval::Perfect(Fourth)),
+        )
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct MajorPentatonic {
+    pub root: Note,
+}
+
+impl Scale for MajorPentatonic {
+    fn intervals(&self) -> Vec<Interval> {
+        vec![
+            Perfect(Unison),
+            Major(Second),
+            Major(Third),
+            Perfect(Fifth),
+            Major(Sixth),
+        ]
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct MinorPentatonic {
+    pub root: Note,
+}
+
+impl Scale for MinorPentatonic {
+    fn intervals(&self) -> Vec<Interval> {
+        vec![
+            Perfect(Unison),
+            Minor(Third),
+            Perfect(Fourth),
+            Perfect(Fifth),
+            Minor(Seventh),
+        ]
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct Blues {
+    pub root: Note,
+}
+
+impl Scale for Blues {
+    fn intervals(&self) -> Vec<Interval> {
+        let mut intervals = MinorPentatonic { root: self.root }.intervals();
+        intervals.push(Augmented(AnyInterval::Perfect(Fourth)));
+        intervals
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct WholeTone {
+    pub root: Note,
+}
+
+impl Scale for WholeTone {
+    fn intervals(&self) -> Vec<Interval> {
+        vec![
+            Perfect(Unison),
+            Major(Second),
+            Major(Third),
+            Augmented(AnyInterval::Perfect(Fourth)),
+            Augmented(AnyInterval::Perfect(Fifth)),
+            Minor(Seventh),
+        ]
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct HalfWholeDiminished {
+    pub root: Note,
+}
+
+pub type DominantDiminished = HalfWholeDiminished;
+
+impl Scale for HalfWholeDiminished {
+    fn intervals(&self) -> Vec<Interval> {
+        vec![
+            Perfect(Unison),
+            Minor(Second),
+            Augmented(AnyInterval::Imperfect(Second)),
+            Major(Third),
+            Augmented(AnyInterval::Perfect(Fourth)),
+            Perfect(Fifth),
+            Major(Sixth),
+            Minor(Seventh),
+        ]
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct WholeHalfDiminished {
+    pub root: Note,
+}
+
+pub type Diminished = WholeHalfDiminished;
+
+impl Scale for WholeHalfDiminished {
+    fn intervals(&self) -> Vec<Interval> {
+        vec![
+            Perfect(Unison),
+            Major(Second),
+            Minor(Third),
+            Perfect(Fourth),
+            Diminshed(AnyInterval::Perfect(Fifth)),
+            Augmented(AnyInterval::Perfect(Fifth)),
+            Major(Sixth),
+            Major(Seventh),
+        ]
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct AugmentedScale {
+    pub root: Note,
+}
+
+impl Scale for AugmentedScale {
+    fn intervals(&self) -> Vec<Interval> {
+        vec![
+            Perfect(Unison),
+            Augmented(AnyInterval::Imperfect(Second)),
+            Major(Third),
+            Perfect(Fifth),
+            Augmented(AnyInterval::Perfect(Fifth)),
+            Major(Seventh),
+        ]
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct BebopDominant {
+    pub root: Note,
+}
+
+impl Scale for BebopDominant {
+    fn intervals(&self) -> Vec<Interval> {
+        let mut intervals = Mixolydian { root: self.root }.intervals();
+        intervals.push(Major(Seventh));
+        intervals
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct BebopMajor {
+    pub root: Note,
+}
+
+impl Scale for BebopMajor {
+    fn intervals(&self) -> Vec<Interval> {
+        let mut intervals = Ionian { root: self.root }.intervals();
+        intervals.push(Augmented(AnyInterval::Perfect(Fifth)));
+        intervals
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+pub struct BebopDorian {
+    pub root: Note,
+}
+
+impl Scale for BebopDorian {
+    fn intervals(&self) -> Vec<Interval> {
+        let mut intervals = Dorian { root: self.root }.intervals();
+        intervals.push(Major(Third));
+        intervals
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+/// A scale defined at runtime by its root and intervals.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomScale {
+    pub root: Note,
+    pub intervals: Vec<Interval>,
+}
+
+impl Scale for CustomScale {
+    fn intervals(&self) -> Vec<Interval> {
+        self.intervals.clone()
+    }
+
+    fn root(&self) -> Note {
+        self.root
+    }
+}
+
+/// The canonical name of every scale known to `CustomScale::from_name`.
+pub const SCALE_NAMES: [&str; 31] = [
+    "ionian",
+    "dorian",
+    "phrygian",
+    "lydian",
+    "mixolydian",
+    "aeolian",
+    "locrian",
+    "harmonic minor",
+    "locrian natural 6",
+    "ionian augmented",
+    "dorian sharp 4",
+    "phrygian dominant",
+    "lydian sharp 2",
+    "super locrian double flat 7",
+    "melodic minor",
+    "dorian flat 2",
+    "lydian augmented",
+    "lydian dominant",
+    "mixolydian flat 6",
+    "locrian natural 2",
+    "altered",
+    "major pentatonic",
+    "minor pentatonic",
+    "blues",
+    "whole tone",
+    "half whole diminished",
+    "whole half diminished",
+    "augmented",
+    "bebop dominant",
+    "bebop major",
+    "bebop dorian",
+];
+
+impl CustomScale {
+    /// Looks up a scale by its name, e.g. `"dorian"` or `"natural minor"`. Case, dashes and
+    /// underscores are ignored.
+    pub fn from_name(root: Note, name: &str) -> Option<CustomScale> {
+        let name = name.trim().to_lowercase().replace(['-', '_'], " ");
+        let intervals = match name.as_str() {
+            "lydian" => Lydian { root }.intervals(),
+            "ionian" | "major" => Ionian { root }.intervals(),
+            "mixolydian" => Mixolydian { root }.intervals(),
+            "dorian" => Dorian { root }.intervals(),
+            "aeolian" | "minor" | "natural minor" => Aeolian { root }.intervals(),
+            "phrygian" => Phrygian { root }.intervals(),
+            "locrian" => Locrian { root }.intervals(),
+            "harmonic minor" => HarmonicMinor { root }.intervals(),
+            "locrian natural 6" | "locrian ♮6" => LocrianNatural6 { root }.intervals(),
+            "ionian augmented" | "ionian ♯5" => IonianAugmented { root }.intervals(),
+            "dorian sharp 4" | "dorian ♯4" | "ukrainian dorian" => {
+                DorianSharp4 { root }.intervals()
+            }
+            "phrygian dominant" => PhrygianDominant { root }.intervals(),
+            "lydian sharp 2" | "lydian ♯2" => LydianSharp2 { root }.intervals(),
+            "super locrian double flat 7" | "altered diminished" | "ultralocrian" => {
+                SuperLocrianDoubleFlat7 { root }.intervals()
+            }
+            "melodic minor" | "jazz minor" => MelodicMinor { root }.intervals(),
+            "dorian flat 2" | "dorian ♭2" | "phrygian natural 6" | "phrygian ♮6" => {
+                DorianFlat2 { root }.intervals()
+            }
+            "lydian augmented" | "lydian ♯5" => LydianAugmented { root }.intervals(),
+            "lydian dominant" | "overtone" => LydianDominant { root }.intervals(),
+            "mixolydian flat 6" | "mixolydian ♭6" | "aeolian dominant" => {
+                MixolydianFlat6 { root }.intervals()
+            }
+            "locrian natural 2" | "locrian ♮2" => LocrianNatural2 { root }.intervals(),
+            "altered" | "super locrian" => Altered { root }.intervals(),
+            "major pentatonic" | "pentatonic" => MajorPentatonic { root }.intervals(),
+            "minor pentatonic" => MinorPentatonic { root }.intervals(),
+            "blues" => Blues { root }.intervals(),
+            "whole tone" => WholeTone { root }.intervals(),
+            "half whole diminished" | "half whole" | "dominant diminished" => {
+                HalfWholeDiminished { root }.intervals()
+            }
+            "whole half diminished" | "whole half" | "diminished" => {
+                WholeHalfDiminished { root }.intervals()
+            }
+            "augmented" => AugmentedScale { root }.intervals(),
+            "bebop dominant" | "bebop" => BebopDominant { root }.intervals(),
+            "bebop major" => BebopMajor { root }.intervals(),
+            "bebop dorian" => BebopDorian { root }.intervals(),
+            _ => return None,
+        };
+        Some(CustomScale { root, intervals })
+    }
+
+    /// Builds a scale from a formula of scale degrees relative to the major scale, such as
+    /// `"1 2 b3 4 5 b6 b7"`. Degrees may be separated by spaces or commas and carry any
+    /// number of `b`, `♭`, `#` or `♯` accidentals. A formula without degrees is an error
+    /// spanning the whole input.
+    pub fn from_formula(root: Note, formula: &str) -> Result<CustomScale, ParseFormulaError> {
+        let mut intervals = vec![];
+
+        let mut start = 0;
+        for (end, c) in formula
+            .char_indices()
+            .chain(std::iter::once((formula.len(), ' ')))
+        {
+            if c.is_whitespace() || c == ',' {
+                if start < end {
+                    let span = start..end;
+                    let interval =
+                        degree(&formula[span.clone()]).ok_or(ParseFormulaError { span })?;
+                    intervals.push(interval);
+                }
+                start = end + c.len_utf8();
+            }
+        }
+
+        if intervals.is_empty() {
+            return Err(ParseFormulaError {
+                span: 0..formula.len(),
+            });
+        }
+        Ok(CustomScale { root, intervals })
+    }
+}
+
+/// Parses a single scale degree such as `b3` or `#11` into its interval above the root.
+fn degree(token: &str) -> Option<Interval> {
+    let number = token.trim_start_matches(['b', '♭', '#', '♯']);
+    let accidentals = &token[..token.len() - number.len()];
+    let shift: i32 = accidentals
+        .chars()
+        .map(|c| if c == '#' || c == '♯' { 1 } else { -1 })
+        .sum();
+
+    let steps = number.parse::<u32>().ok()?.checked_sub(1)?;
+    // Up to the fifteenth, see `Interval::from_steps`.
+    if steps > 14 {
+        return None;
+    }
+    let name = [
+        NoteName::C,
+        NoteName::D,
+        NoteName::E,
+        NoteName::F,
+        NoteName::G,
+        NoteName::A,
+        NoteName::B,
+    ][steps as usize % 7];
+    let size = name.semitones() + 12 * (steps / 7) as i32 + shift;
+    if size < 0 {
+        return None;
+    }
+
+    Interval::from_steps(steps, size as u32)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFormulaError {
+    /// Byte range of the scale degree that could not be read.
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseFormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid scale degree at {}..{}",
+            self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseFormulaError {}
+
+fn sorted(intervals: &[Interval]) -> Vec<Interval> {
+    let mut intervals = intervals.to_vec();
+    intervals.sort_by_key(|a| (a.size(), a.diatonic_steps()));
+    intervals.dedup();
+    intervals
+}
+
+/// Returns the canonical name of the scale with exactly these intervals, if it has one.
+pub fn name_of(intervals: &[Interval]) -> Option<&'static str> {
+    let intervals = sorted(intervals);
+    let root = crate::notes::octave(crate::c!(), 4);
+    SCALE_NAMES.into_iter().find(|name| {
+        CustomScale::from_name(root, name).map(|scale| sorted(&scale.intervals))
+            == Some(intervals.clone())
+    })
+}
+
+/// Returns the intervals of the `n`-th mode of a scale, counting from 1, re-expressed
+/// relative to its own root. Compound intervals are reduced to simple ones first. Each note
+/// keeps its letter name where possible; a note that cannot be reached with a single
+/// augmented or diminished quality, such as D♯ to D♭ in the half-whole diminished scale, is
+/// respelled by a neighbouring letter. Returns `None` for `n` 0, for an empty scale, or if a
+/// note cannot be named at all.
+pub fn mode(intervals: &[Interval], n: usize) -> Option<Vec<Interval>> {
+    let simple: Vec<Interval> = intervals.iter().map(|i| i.simple().0).collect();
+    let intervals = sorted(&simple);
+    if n == 0 || intervals.is_empty() {
+        return None;
+    }
+    let index = (n - 1) % intervals.len();
+    let root = intervals[index];
+
+    let mut mode = intervals
+        .iter()
+        .enumerate()
+        .map(|(i, interval)| {
+            let wrapped = i < index;
+            let steps = interval.diatonic_steps() as i32 - root.diatonic_steps() as i32
+                + if wrapped { 7 } else { 0 };
+            let size = interval.size() as i32 + if wrapped { 12 } else { 0 } - root.size() as i32;
+            if size < 0 {
+                return None;
+            }
+
+            [steps, steps - 1, steps + 1]
+                .into_iter()
+                .filter(|&steps| (0..=7).contains(&steps))
+                .find_map(|steps| Interval::from_steps(steps as u32, size as u32))
+        })
+        .collect::<Option<Vec<Interval>>>()?;
+
+    mode.sort_by_key(|a| (a.size(), a.diatonic_steps()));
+    Some(mode)
+}
+
+/// Iterates over all modes of a scale, in order, with their canonical names where known.
+/// Modes that `mode` cannot name are left out.
+pub fn modes(
+    intervals: &[Interval],
+) -> impl Iterator<Item = (Option<&'static str>, Vec<Interval>)> + '_ {
+    (1..=sorted(intervals).len()).filter_map(move |n| {
+        let mode = mode(intervals, n)?;
+        Some((name_of(&mode), mode))
+    })
+}
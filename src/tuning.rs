@@ -0,0 +1,40 @@
+use crate::notes::{Note, Spelling};
+
+/// Twelve-tone equal temperament relative to a reference frequency for A4.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tuning {
+    pub a4: f64,
+}
+
+impl Tuning {
+    pub const CONCERT: Tuning = Tuning { a4: 440.0 };
+    pub const ORCHESTRAL: Tuning = Tuning { a4: 442.0 };
+    pub const BAROQUE: Tuning = Tuning { a4: 415.0 };
+
+    /// Position of A4 in semitones above C0.
+    const A4: i32 = 4 * 12 + 9;
+
+    pub fn frequency(&self, note: &Note) -> f64 {
+        self.a4 * 2f64.powf((note.position() - Tuning::A4) as f64 / 12.0)
+    }
+
+    /// Returns the note closest to `hz`, spelled according to `spelling`, and how many cents
+    /// `hz` lies above it, between -50 and 50. Returns `None` for frequencies that are not
+    /// positive or lie below octave 0.
+    pub fn nearest(&self, hz: f64, spelling: &Spelling) -> Option<(Note, f64)> {
+        if !(hz > 0.0 && hz.is_finite()) {
+            return None;
+        }
+
+        let semitones = 12.0 * (hz / self.a4).log2() + Tuning::A4 as f64;
+        let position = semitones.round();
+        let note = Note::from_position(position as i32, spelling)?;
+        Some((note, (semitones - position) * 100.0))
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning::CONCERT
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use crate::{
+    chords::Chord,
+    intervals::{Diatonic, Interval},
+    notes::{Note, Notes, Pitch},
+};
+
+/// Rules a voice leading has to follow. By default voices move at most an octave and do
+/// not cross.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Constraints {
+    /// Lowest and highest note of each voice, from the bottom voice up. Voices without a
+    /// range may move at most an octave.
+    pub ranges: Vec<(Note, Note)>,
+    /// Whether a voice may move above the voice above it.
+    pub crossing: bool,
+    /// Whether a voice holding a tone of the next chord must keep it. Without this, common
+    /// tones are usually kept anyway since that costs no movement.
+    pub common_tones: bool,
+}
+
+/// Finds the voicing of `next` that moves the voices of `previous` by the fewest semitones
+/// in total, with one note per voice. Voices are taken from low to high.
+///
+/// Every tone of the chord is used if there are enough voices. Otherwise the fifth is left
+/// out first, then the root, then tensions, so that the third and seventh remain. The bass
+/// of a slash chord goes to the lowest voice. Returns `None` if no voicing satisfies the
+/// constraints.
+pub fn lead(previous: &Notes, next: &Chord, constraints: &Constraints) -> Option<Notes> {
+    let mut previous = previous.clone();
+    previous.sort_by_pitch();
+    let voices = previous.0.len();
+    if voices == 0 {
+        return None;
+    }
+
+    let mut intervals = next.intervals();
+    intervals.sort_by_key(priority);
+    let tones: Vec<Pitch> = intervals.iter().map(|&i| next.root.pitch.leap(i)).collect();
+    // A slash bass outside the chord takes a voice from the chord tones.
+    let foreign_bass = next.bass.is_some_and(|bass| !tones.contains(&bass));
+    let required: Vec<Pitch> = tones
+        .iter()
+        .copied()
+        .take(voices - foreign_bass as usize)
+        .collect();
+
+    let candidates: Vec<Vec<(i32, Note)>> = previous
+        .0
+        .iter()
+        .enumerate()
+        .map(|(voice, &from)| {
+            let (low, high) = match constraints.ranges.get(voice) {
+                Some(&(low, high)) => (low.position(), high.position()),
+                None => (from.position() - 12, from.position() + 12),
+            };
+            let pitches: Vec<Pitch> = match next.bass {
+                Some(bass) if voice == 0 => vec![bass],
+                _ => tones.clone(),
+            };
+
+            let mut candidates: Vec<(i32, Note)> = pitches
+                .iter()
+                .flat_map(|&pitch| (0..=10).map(move |octave| Note { pitch, octave }))
+                .filter(|n| n.position() >= low && n.position() <= high)
+                .map(|n| ((n.position() - from.position()).abs(), n))
+                .collect();
+            if constraints.common_tones
+                && pitches
+                    .iter()
+                    .any(|p| p.pitch_class() == from.pitch.pitch_class())
+            {
+                candidates.retain(|(movement, _)| *movement == 0);
+            }
+            candidates.sort_by(|(a, m), (b, n)| a.cmp(b).then(m.cmp_pitch(n)));
+            candidates
+        })
+        .collect();
+
+    let mut search = Search {
+        candidates: &candidates,
+        required: &required,
+        crossing: constraints.crossing,
+        chosen: Vec::with_capacity(voices),
+        best: None,
+    };
+    search.run(0);
+    search.best.map(|(_, notes)| Notes(notes))
+}
+
+/// Leads `start` through `chords` one chord at a time, see `lead`. Returns the voicing of
+/// every chord, or `None` if one of them cannot be reached.
+pub fn lead_progression(
+    start: &Notes,
+    chords: &[Chord],
+    constraints: &Constraints,
+) -> Option<Vec<Notes>> {
+    let mut voicings: Vec<Notes> = vec![];
+    for chord in chords {
+        let previous = voicings.last().unwrap_or(start);
+        voicings.push(lead(previous, chord, constraints)?);
+    }
+    Some(voicings)
+}
+
+/// Total number of semitones the voices move from `from` to `to`, voice by voice.
+pub fn movement(from: &Notes, to: &Notes) -> i32 {
+    from.0
+        .iter()
+        .zip(&to.0)
+        .map(|(a, b)| (a.position() - b.position()).abs())
+        .sum()
+}
+
+/// Which chord tones to keep when there are fewer voices than tones, most important first.
+fn priority(interval: &Interval) -> usize {
+    match interval.diatonic_steps() % 7 {
+        2 => 0,
+        6 => 1,
+        4 if interval.size() != 7 => 2,
+        1 | 3 | 5 => 2,
+        0 => 3,
+        _ => 4,
+    }
+}
+
+/// Depth-first search over one candidate note per voice, cheapest first, that skips
+/// branches costing at least as much as the best voicing found so far.
+struct Search<'a> {
+    candidates: &'a [Vec<(i32, Note)>],
+    required: &'a [Pitch],
+    crossing: bool,
+    chosen: Vec<Note>,
+    best: Option<(i32, Vec<Note>)>,
+}
+
+impl Search<'_> {
+    fn run(&mut self, cost: i32) {
+        if self.best.as_ref().is_some_and(|(best, _)| *best <= cost) {
+            return;
+        }
+        let voice = self.chosen.len();
+        if voice == self.candidates.len() {
+            let pitches: HashSet<Pitch> = self.chosen.iter().map(|n| n.pitch).collect();
+            if self.required.iter().all(|p| pitches.contains(p)) {
+                self.best = Some((cost, self.chosen.clone()));
+            }
+            return;
+        }
+
+        for &(movement, note) in &self.candidates[voice] {
+            let crossed = self
+                .chosen
+                .last()
+                .is_some_and(|below| note.position() < below.position());
+            if crossed && !self.crossing {
+                continue;
+            }
+            self.chosen.push(note);
+            self.run(cost + movement);
+            self.chosen.pop();
+        }
+    }
+}
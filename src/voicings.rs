@@ -0,0 +1,172 @@
+use std::cmp::Ordering;
+
+use crate::{
+    chords::Chord,
+    intervals::{Diatonic, ImperfectInterval, Interval},
+    notes::{Note, Notes, Pitch},
+};
+
+/// How the tones of a chord are spread across octaves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Voicings {
+    /// All tones stacked as closely as possible above the lowest one.
+    Close,
+    /// Close position with every other tone above the lowest raised an octave.
+    Open,
+    /// Close position with the second tone from the top dropped an octave.
+    Drop2,
+    /// Close position with the third tone from the top dropped an octave.
+    Drop3,
+    /// Root, third and seventh, or the sixth of a sixth chord.
+    Shell,
+    /// Third, fifth, seventh and ninth without the root. A thirteenth takes the place of the
+    /// fifth, and the ninth is added if the chord has none.
+    RootlessA,
+    /// Seventh, ninth, third and fifth, the upper half of `RootlessA` moved below.
+    RootlessB,
+}
+
+/// Voices `chord` and returns every octave placement of the voicing that lies between `low`
+/// and `high`, inclusive, from low to high. Each voicing lists its notes from low to high.
+///
+/// `inversion` rotates the tones before they are stacked, so 1 puts the third at the bottom
+/// of a close voicing. For shell voicings the root stays at the bottom and 1 puts the
+/// seventh below the third, and `RootlessA` with inversion 2 equals `RootlessB`. The bass of
+/// a slash chord is added below the voicing, unless it is already the lowest tone.
+///
+/// Returns no voicings if the chord lacks the tones the voicing needs, such as the third
+/// and seventh of a shell voicing, or none fit the range.
+pub fn voice(
+    chord: &Chord,
+    voicing: Voicings,
+    inversion: usize,
+    low: Note,
+    high: Note,
+) -> Vec<Notes> {
+    let root = chord.root.pitch;
+    let intervals = chord.intervals();
+    let find = |steps: u32| intervals.iter().find(|i| i.diatonic_steps() == steps);
+
+    let rotated = |tones: &[Interval]| -> Vec<Pitch> {
+        let mut pitches: Vec<Pitch> = tones.iter().map(|&i| root.leap(i)).collect();
+        if !pitches.is_empty() {
+            let len = pitches.len();
+            pitches.rotate_left(inversion % len);
+        }
+        pitches
+    };
+
+    let mut notes = match voicing {
+        Voicings::Close | Voicings::Open | Voicings::Drop2 | Voicings::Drop3 => {
+            let mut notes = stack(&rotated(&intervals));
+            let len = notes.len();
+            let lower = |notes: &mut Vec<Note>, from_top: usize| {
+                if len < from_top + 2 {
+                    return false;
+                }
+                match notes[len - 1 - from_top].octave.checked_sub(1) {
+                    Some(octave) => notes[len - 1 - from_top].octave = octave,
+                    None => return false,
+                }
+                true
+            };
+            let voiced = match voicing {
+                Voicings::Open => {
+                    for note in notes.iter_mut().skip(1).step_by(2) {
+                        note.octave += 1;
+                    }
+                    true
+                }
+                Voicings::Drop2 => lower(&mut notes, 1),
+                Voicings::Drop3 => lower(&mut notes, 2),
+                _ => true,
+            };
+            if !voiced {
+                return vec![];
+            }
+            notes
+        }
+        Voicings::Shell => {
+            let (Some(&third), Some(&seventh)) = (find(2), find(6).or_else(|| find(5))) else {
+                return vec![];
+            };
+            let mut pitches = vec![root];
+            pitches.extend(rotated(&[third, seventh]));
+            stack(&pitches)
+        }
+        Voicings::RootlessA | Voicings::RootlessB => {
+            let fifth = find(12).or_else(|| find(4));
+            let seventh = find(6).or_else(|| find(5));
+            let ninth = find(8)
+                .copied()
+                .unwrap_or(Interval::Major(ImperfectInterval::Ninth));
+            let (Some(&third), Some(&fifth), Some(&seventh)) = (find(2), fifth, seventh) else {
+                return vec![];
+            };
+            let mut pitches = rotated(&[third, fifth, seventh, ninth]);
+            if voicing == Voicings::RootlessB {
+                pitches.rotate_left(2);
+            }
+            stack(&pitches)
+        }
+    };
+
+    notes.sort_by(Note::cmp_pitch);
+    if let (Some(bass), Some(lowest)) = (chord.bass, notes.first().copied()) {
+        if bass != lowest.pitch {
+            let mut bass = Note {
+                pitch: bass,
+                octave: lowest.octave + 1,
+            };
+            while bass.position() >= lowest.position() {
+                match bass.octave.checked_sub(1) {
+                    Some(octave) => bass.octave = octave,
+                    None => return vec![],
+                }
+            }
+            notes.insert(0, bass);
+        }
+    }
+
+    place(&notes, low, high)
+}
+
+/// Stacks each pitch at the lowest octave above the one before it, starting in octave 4.
+fn stack(pitches: &[Pitch]) -> Vec<Note> {
+    let mut notes: Vec<Note> = vec![];
+    for &pitch in pitches {
+        let mut note = Note { pitch, octave: 4 };
+        if let Some(previous) = notes.last() {
+            note.octave = previous.octave - 1;
+            while note.cmp_pitch(previous) != Ordering::Greater {
+                note.octave += 1;
+            }
+        }
+        notes.push(note);
+    }
+    notes
+}
+
+/// Moves `notes` by whole octaves into every position between `low` and `high`.
+fn place(notes: &[Note], low: Note, high: Note) -> Vec<Notes> {
+    let (Some(first), Some(last)) = (notes.first(), notes.last()) else {
+        return vec![];
+    };
+    let lowest = -(first.position() - low.position()).div_euclid(12);
+    let highest = (high.position() - last.position()).div_euclid(12);
+
+    (lowest..=highest)
+        .filter_map(|shift| {
+            notes
+                .iter()
+                .map(|note| {
+                    Some(Note {
+                        pitch: note.pitch,
+                        octave: u32::try_from(note.octave as i32 + shift).ok()?,
+                    })
+                })
+                .collect::<Option<Vec<Note>>>()
+                .map(Notes)
+        })
+        .collect()
+}
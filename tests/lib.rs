@@ -1,13 +1,39 @@
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::{cmp::Ordering, collections::HashSet};
 
     use scales::{
-        a, aes, ais, b, bes, bis, c, ces, cis, d, des, dis, e, ees, eis, f, fes, fis, g, ges, gis,
-        intervals::{ImperfectInterval::*, Interval::*, PerfectInterval::*},
-        note_a, note_b, note_c, note_ces, note_d, note_dis, note_e, note_ees, note_f, note_g,
-        notes::{octave, pitches, pretty_pitches},
-        scales::{spell, Aeolian, Dorian, Ionian, Locrian, Lydian, Major, Mixolydian, Phrygian},
+        a, aes, ais, b, bes, bis, c, ces,
+        chords::{self, Alterations, Chord, Extensions, Omissions, ParseChordErrorKind},
+        cis, d, des, dis,
+        durations::Duration,
+        e, ees, eis, f, fes, fis, g, ges, gis,
+        intervals::{
+            AnyInterval, DirectedInterval, Direction, ImperfectInterval::*, Interval::*,
+            PerfectInterval::*,
+        },
+        keys::KeySignature,
+        lilypond::{Clef, Element, Mode, Octaves, Score, Staff},
+        midi::{Format, MidiFile, ParseMidiErrorKind, Track},
+        musicxml::{self, ParseMusicXmlErrorKind, Part},
+        note_a, note_aes, note_b, note_c, note_ces, note_d, note_dis, note_e, note_ees, note_f,
+        note_g,
+        notes::{
+            octave, pitches, pretty_pitches, FormatAsCode, Note, NoteName, Notes,
+            ParseNoteErrorKind, Pitch, Spelling,
+        },
+        roman::{self, ParseRomanNumeralErrorKind, RomanNumeral, Sevenths},
+        scales::{
+            mode, modes, spell, Aeolian, Altered, AugmentedScale, BebopDominant, Blues,
+            CustomScale, Dorian, DorianFlat2, DorianSharp4, HalfWholeDiminished, HarmonicMinor,
+            Ionian, IonianAugmented, Locrian, LocrianNatural2, LocrianNatural6, Lydian,
+            LydianAugmented, LydianDominant, LydianSharp2, Major, MajorPentatonic, MelodicMinor,
+            MinorPentatonic, Mixolydian, MixolydianFlat6, Phrygian, PhrygianDominant, Qualities,
+            Scale, SuperLocrianDoubleFlat7, Tensions, WholeHalfDiminished, WholeTone,
+        },
+        tuning::Tuning,
+        voice_leading::{self, Constraints},
+        voicings::{voice, Voicings},
     };
 
     #[test]
@@ -118,4 +144,1674 @@ mod tests {
             assert_eq!(pitches(&mode), c_pitches);
         }
     }
+
+    #[test]
+    fn test_interval_to() {
+        assert_eq!(
+            note_ees!(4).interval_to(&note_b!(4)),
+            Some(Augmented(AnyInterval::Perfect(Fifth)).into())
+        );
+        assert_eq!(
+            note_c!(4).interval_to(&octave(bis!(), 3)),
+            Some(DirectedInterval {
+                interval: Diminshed(AnyInterval::Imperfect(Second)),
+                octaves: 0,
+                direction: Direction::Down,
+            })
+        );
+        assert_eq!(
+            note_c!(4).interval_to(&note_d!(6)),
+            Some(DirectedInterval {
+                interval: Major(Second),
+                octaves: 2,
+                direction: Direction::Up,
+            })
+        );
+        assert_eq!(ees!().interval_to(&c!()), Some(Major(Sixth)));
+        assert_eq!(
+            c!().interval_to(&ces!()),
+            Some(Diminshed(AnyInterval::Perfect(Octave)))
+        );
+
+        use NoteName::*;
+        let notes: Vec<Note> = (2..6)
+            .flat_map(|octave| [C, D, E, F, G, A, B].map(move |name| (name, octave)))
+            .flat_map(|(name, octave)| {
+                (-1..=1).map(move |accidentals| Note {
+                    pitch: Pitch { name, accidentals },
+                    octave,
+                })
+            })
+            .collect();
+
+        for a in &notes {
+            for b in &notes {
+                let naturals = a.pitch.accidentals == 0 && b.pitch.accidentals == 0;
+                let Some(interval) = a.interval_to(b) else {
+                    assert!(!naturals, "{} to {}", a, b);
+                    continue;
+                };
+                assert_eq!(a.leap(interval), *b, "{} to {}", a, b);
+                assert_eq!(a.leap(interval).position() - a.position(), interval.size());
+                assert_eq!(
+                    a.pitch.leap(a.pitch.interval_to(&b.pitch).unwrap()),
+                    b.pitch
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_compound_intervals() {
+        assert_eq!(note_c!(4).leap(Major(Ninth)), note_d!(5));
+        assert_eq!(note_g!(4).leap(Perfect(Eleventh)), note_c!(6));
+        assert_eq!(note_ees!(4).leap(Minor(Thirteenth)), octave(ces!(), 6));
+        assert_eq!(note_c!(4).leap(Perfect(Fifteenth)), note_c!(6));
+        assert_eq!(note_d!(5).fall(Major(Ninth)), note_c!(4));
+        assert_eq!(
+            octave(d!(), 1).checked_fall(Major(Ninth)),
+            Some(octave(c!(), 0))
+        );
+        assert_eq!(octave(c!(), 1).checked_fall(Major(Ninth)), None);
+        assert_eq!(octave(c!(), 0).checked_fall(Minor(Second)), None);
+        assert_eq!(
+            octave(c!(), 0).checked_leap(Minor(Second)),
+            Some(octave(des!(), 0))
+        );
+        assert_eq!(d!().leap(Augmented(AnyInterval::Imperfect(Ninth))), eis!());
+
+        assert_eq!(Major(Ninth).simple(), (Major(Second), 1));
+        assert_eq!(Perfect(Fifteenth).simple(), (Perfect(Octave), 1));
+        assert_eq!(Minor(Third).compound(1), Some(Minor(Tenth)));
+        assert_eq!(Minor(Third).compound(2), None);
+
+        let compounds = [
+            Minor(Ninth),
+            Major(Ninth),
+            Augmented(AnyInterval::Imperfect(Ninth)),
+            Major(Tenth),
+            Perfect(Eleventh),
+            Augmented(AnyInterval::Perfect(Eleventh)),
+            Diminshed(AnyInterval::Perfect(Twelfth)),
+            Perfect(Twelfth),
+            Minor(Thirteenth),
+            Major(Thirteenth),
+            Minor(Fourteenth),
+            Perfect(Fifteenth),
+        ];
+        for interval in compounds {
+            let (simple, octaves) = interval.simple();
+            let mut expected = note_c!(4).leap(simple);
+            expected.octave += octaves;
+            assert_eq!(note_c!(4).leap(interval), expected);
+            assert_eq!(
+                note_c!(4).interval_to(&expected),
+                Some(DirectedInterval {
+                    interval: simple,
+                    octaves,
+                    direction: Direction::Up,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_chords() {
+        let mut chord = Chord::new(note_c!(4), Qualities::Major);
+        assert_eq!(chord.to_string(), "C");
+        assert_eq!(
+            chords::spell(&chord).0,
+            vec![note_c!(4), note_e!(4), note_g!(4)]
+        );
+
+        chord.tensions = vec![Tensions::Seventh, Tensions::Ninth];
+        chord.alterations = vec![Alterations::SharpEleventh];
+        assert_eq!(chord.to_string(), "C9♯11");
+        assert_eq!(
+            chords::spell(&chord).0,
+            vec![
+                note_c!(4),
+                note_e!(4),
+                note_g!(4),
+                octave(bes!(), 4),
+                note_d!(5),
+                octave(fis!(), 5)
+            ]
+        );
+
+        let mut chord = Chord::new(octave(b!(), 3), Qualities::Minor);
+        chord.tensions = vec![Tensions::Seventh];
+        chord.alterations = vec![Alterations::FlatFifth];
+        assert_eq!(chord.to_string(), "Bm7♭5");
+        assert_eq!(
+            pitches(&chords::spell(&chord)),
+            HashSet::from_iter([b!(), d!(), f!(), a!()])
+        );
+
+        let mut chord = Chord::new(note_c!(4), Qualities::Diminshed);
+        chord.tensions = vec![Tensions::Seventh];
+        assert_eq!(chord.to_string(), "Cdim7");
+        assert_eq!(chords::spell(&chord).0[3].pitch.accidentals, -2);
+
+        let mut chord = Chord::new(note_ees!(4), Qualities::Major);
+        chord.tensions = vec![Tensions::Sixth, Tensions::Ninth];
+        chord.bass = Some(g!());
+        assert_eq!(chord.to_string(), "E♭6/9/G");
+        assert_eq!(chords::spell(&chord).0[0], note_g!(3));
+        chord.root.octave = 0;
+        assert_eq!(chords::spell(&chord).0[0], octave(g!(), 0));
+
+        let mut chord = Chord::new(note_g!(4), Qualities::Major);
+        chord.tensions = vec![Tensions::Seventh];
+        chord.alterations = chords::ALTERED.to_vec();
+        assert_eq!(chord.to_string(), "G7alt");
+        assert_eq!(
+            pitches(&chords::spell(&chord)),
+            HashSet::from_iter([g!(), b!(), des!(), dis!(), f!(), aes!(), ais!()])
+        );
+
+        let mut chord = Chord::new(note_e!(4), Qualities::Minor);
+        chord.alterations = vec![Alterations::SharpFifth];
+        assert_eq!(chord.to_string(), "Em(♯5)");
+
+        let mut chord = Chord::new(note_c!(4), Qualities::Minor);
+        chord.tensions = vec![Tensions::MajorSeventh];
+        assert_eq!(chord.to_string(), "Cm(maj7)");
+
+        let mut chord = Chord::new(note_d!(4), Qualities::Sus4);
+        chord.tensions = vec![Tensions::Seventh];
+        assert_eq!(chord.to_string(), "D7sus4");
+    }
+
+    #[test]
+    fn test_parse_chords() {
+        let chord: Chord = "C#m7b5".parse().unwrap();
+        assert_eq!(chord.root, octave(cis!(), 4));
+        assert_eq!(chord.quality, Qualities::Minor);
+        assert_eq!(chord.tensions, vec![Tensions::Seventh]);
+        assert_eq!(chord.alterations, vec![Alterations::FlatFifth]);
+
+        let chord: Chord = "Bbmaj9#11/D".parse().unwrap();
+        assert_eq!(chord.root, octave(bes!(), 4));
+        assert_eq!(
+            chord.tensions,
+            vec![Tensions::MajorSeventh, Tensions::Ninth]
+        );
+        assert_eq!(chord.alterations, vec![Alterations::SharpEleventh]);
+        assert_eq!(chord.bass, Some(d!()));
+        assert_eq!(chord.to_string(), "B♭maj9♯11/D");
+
+        let chord: Chord = "F7alt".parse().unwrap();
+        assert_eq!(chord.to_string(), "F7alt");
+
+        let chord: Chord = "G7sus4(b9)".parse().unwrap();
+        assert_eq!(chord.quality, Qualities::Sus4);
+        assert_eq!(chord.alterations, vec![Alterations::FlatNinth]);
+
+        let chord: Chord = "Cadd9omit5".parse().unwrap();
+        assert_eq!(chord.omissions, vec![Omissions::Fifth]);
+        assert_eq!(
+            chords::spell(&chord).0,
+            vec![note_c!(4), note_e!(4), note_d!(5)]
+        );
+
+        for symbol in [
+            "C",
+            "Cm",
+            "C5",
+            "Cdim",
+            "Cdim7",
+            "Caug",
+            "C6",
+            "Cm6",
+            "C6/9",
+            "C7",
+            "Cmaj7",
+            "Cm(maj7)",
+            "Cm7",
+            "C9",
+            "Cm11",
+            "C13",
+            "C7♯9",
+            "C7♯5♭9",
+            "Csus2",
+            "C7sus4",
+            "Cadd9",
+            "Em(♯5)",
+            "E♭m7♭5",
+            "F♯7alt",
+            "C/E",
+            "Am7/G",
+        ] {
+            assert_eq!(symbol.parse::<Chord>().unwrap().to_string(), symbol);
+        }
+        assert_eq!("Cø".parse::<Chord>().unwrap().to_string(), "Cm7♭5");
+        assert_eq!("C-7".parse::<Chord>().unwrap().to_string(), "Cm7");
+        assert_eq!("CΔ7".parse::<Chord>().unwrap().to_string(), "Cmaj7");
+
+        let error = "".parse::<Chord>().unwrap_err();
+        assert_eq!(error.kind, ParseChordErrorKind::Empty);
+        let error = "H7".parse::<Chord>().unwrap_err();
+        assert_eq!(
+            (error.kind, error.span),
+            (ParseChordErrorKind::InvalidRoot, 0..1)
+        );
+        let error = "C7b10".parse::<Chord>().unwrap_err();
+        assert_eq!(
+            (error.kind, error.span),
+            (ParseChordErrorKind::UnexpectedSymbol, 2..5)
+        );
+        let error = "Cm7/X".parse::<Chord>().unwrap_err();
+        assert_eq!(
+            (error.kind, error.span),
+            (ParseChordErrorKind::InvalidBass, 4..5)
+        );
+        let error = "Cmsus4".parse::<Chord>().unwrap_err();
+        assert_eq!(error.kind, ParseChordErrorKind::ConflictingQuality);
+        let error = "C7xyz".parse::<Chord>().unwrap_err();
+        assert_eq!(error.span, 2..3);
+    }
+
+    #[test]
+    fn test_recognize_chords() {
+        let names = |notes: Vec<_>| -> Vec<String> {
+            chords::recognize(&Notes(notes))
+                .iter()
+                .map(|c| c.to_string())
+                .collect()
+        };
+
+        let candidates = names(vec![note_e!(4), note_g!(4), note_c!(5)]);
+        assert_eq!(candidates[0], "C/E");
+        assert!(candidates.contains(&"Em(♯5)".to_string()));
+
+        assert_eq!(names(vec![note_c!(4), note_e!(4), note_g!(4)])[0], "C");
+        assert_eq!(
+            names(vec![note_g!(3), note_b!(3), note_d!(4), note_f!(4)])[0],
+            "G7"
+        );
+        assert_eq!(
+            names(vec![octave(bes!(), 3), note_d!(4), note_f!(4), note_a!(4)])[0],
+            "B♭maj7"
+        );
+        assert_eq!(
+            names(vec![
+                note_c!(4),
+                note_ees!(4),
+                octave(ges!(), 4),
+                note_a!(4)
+            ])[..2],
+            ["Cdim7", "Cm6(♭5)"]
+        );
+        assert_eq!(
+            names(vec![
+                note_d!(3),
+                note_c!(4),
+                note_f!(4),
+                note_a!(4),
+                note_e!(5)
+            ])[0],
+            "Dm9"
+        );
+        assert_eq!(
+            names(vec![
+                note_c!(3),
+                note_g!(3),
+                note_c!(4),
+                note_d!(4),
+                note_g!(4)
+            ])[0],
+            "Csus2"
+        );
+
+        assert_eq!(
+            names(vec![octave(b!(), 3), note_d!(4), note_f!(4), note_a!(4)])[0],
+            "Bm7♭5"
+        );
+
+        let pitches = HashSet::from_iter([f!(), a!(), c!(), e!()]);
+        let candidates = chords::recognize_pitches(&pitches);
+        assert_eq!(candidates[0].to_string(), "Fmaj7");
+        assert!(candidates.iter().all(|c| c.bass.is_none()));
+    }
+
+    #[test]
+    fn test_custom_scales() {
+        let dorian = CustomScale::from_name(note_d!(4), "Dorian").unwrap();
+        assert_eq!(spell(&dorian), spell(Dorian { root: note_d!(4) }));
+        assert_eq!(
+            CustomScale::from_name(note_a!(4), "natural-minor").map(spell),
+            Some(spell(Aeolian { root: note_a!(4) }))
+        );
+        assert_eq!(CustomScale::from_name(note_a!(4), "klingon"), None);
+
+        let phrygian = CustomScale::from_formula(note_e!(4), "1, b2, b3, 4, 5, b6, b7").unwrap();
+        assert_eq!(spell(&phrygian), spell(Phrygian { root: note_e!(4) }));
+        let lydian = CustomScale::from_formula(note_f!(4), "1 2 3 ♯4 5 6 7").unwrap();
+        assert_eq!(lydian.intervals, Lydian { root: note_f!(4) }.intervals());
+
+        let error = CustomScale::from_formula(note_c!(4), "1 2 x3 4").unwrap_err();
+        assert_eq!(error.span, 4..6);
+        assert!(CustomScale::from_formula(note_c!(4), "1 ##4").is_err());
+        let error = CustomScale::from_formula(note_c!(4), "1 4294967295").unwrap_err();
+        assert_eq!(error.span, 2..12);
+        assert!(CustomScale::from_formula(note_c!(4), "1 15").is_ok());
+        assert!(CustomScale::from_formula(note_c!(4), "1 16").is_err());
+        let error = CustomScale::from_formula(note_c!(4), " , ").unwrap_err();
+        assert_eq!(error.span, 0..3);
+
+        let scales: Vec<Box<dyn Scale>> = vec![
+            Box::new(Ionian { root: note_c!(4) }),
+            Box::new(phrygian),
+            Box::new(CustomScale::from_name(note_g!(4), "mixolydian").unwrap()),
+        ];
+        for scale in scales {
+            assert_eq!(spell(&scale).0[0], scale.root());
+            assert_eq!(spell(scale).0.len(), 7);
+        }
+    }
+
+    #[test]
+    fn test_minor_modes() {
+        let a_harmonic: HashSet<_> = [a!(), b!(), c!(), d!(), e!(), f!(), gis!()]
+            .iter()
+            .cloned()
+            .collect();
+
+        let modes = [
+            spell(HarmonicMinor { root: note_a!(4) }),
+            spell(LocrianNatural6 { root: note_b!(4) }),
+            spell(IonianAugmented { root: note_c!(4) }),
+            spell(DorianSharp4 { root: note_d!(4) }),
+            spell(PhrygianDominant { root: note_e!(4) }),
+            spell(LydianSharp2 { root: note_f!(4) }),
+            spell(SuperLocrianDoubleFlat7 {
+                root: octave(gis!(), 4),
+            }),
+        ];
+
+        for mode in modes {
+            assert_eq!(pitches(&mode), a_harmonic);
+        }
+
+        let c_melodic: HashSet<_> = [c!(), d!(), ees!(), f!(), g!(), a!(), b!()]
+            .iter()
+            .cloned()
+            .collect();
+
+        let modes = [
+            spell(MelodicMinor { root: note_c!(4) }),
+            spell(DorianFlat2 { root: note_d!(4) }),
+            spell(LydianAugmented { root: note_ees!(4) }),
+            spell(LydianDominant { root: note_f!(4) }),
+            spell(MixolydianFlat6 { root: note_g!(4) }),
+            spell(LocrianNatural2 { root: note_a!(4) }),
+            spell(Altered { root: note_b!(4) }),
+        ];
+
+        for mode in modes {
+            assert_eq!(pitches(&mode), c_melodic);
+        }
+
+        assert_eq!(
+            pitches(&spell(Altered { root: note_g!(4) })),
+            HashSet::from_iter([g!(), aes!(), bes!(), ces!(), des!(), ees!(), f!()])
+        );
+        assert_eq!(
+            CustomScale::from_name(note_e!(4), "Phrygian dominant").map(spell),
+            Some(spell(PhrygianDominant { root: note_e!(4) }))
+        );
+    }
+
+    #[test]
+    fn test_non_heptatonic_scales() {
+        let spelled = |notes: Notes| -> Vec<Pitch> { notes.into_iter().map(|n| n.pitch).collect() };
+
+        assert_eq!(
+            spelled(spell(MajorPentatonic { root: note_c!(4) })),
+            vec![c!(), d!(), e!(), g!(), a!()]
+        );
+        assert_eq!(
+            spelled(spell(MinorPentatonic { root: note_a!(4) })),
+            vec![a!(), c!(), d!(), e!(), g!()]
+        );
+        assert_eq!(
+            spelled(spell(Blues { root: note_c!(4) })),
+            vec![c!(), ees!(), f!(), fis!(), g!(), bes!()]
+        );
+        assert_eq!(
+            spelled(spell(WholeTone { root: note_c!(4) })),
+            vec![c!(), d!(), e!(), fis!(), gis!(), bes!()]
+        );
+        assert_eq!(
+            spelled(spell(HalfWholeDiminished { root: note_c!(4) })),
+            vec![c!(), des!(), dis!(), e!(), fis!(), g!(), a!(), bes!()]
+        );
+        assert_eq!(
+            spelled(spell(WholeHalfDiminished { root: note_c!(4) })),
+            vec![c!(), d!(), ees!(), f!(), ges!(), gis!(), a!(), b!()]
+        );
+        assert_eq!(
+            spelled(spell(AugmentedScale { root: note_c!(4) })),
+            vec![c!(), dis!(), e!(), g!(), gis!(), b!()]
+        );
+        assert_eq!(
+            spelled(spell(BebopDominant { root: note_g!(4) })),
+            vec![g!(), a!(), b!(), c!(), d!(), e!(), f!(), fis!()]
+        );
+
+        let blues = spell(Blues { root: note_a!(3) });
+        assert_eq!(blues.0.last(), Some(&note_g!(4)));
+        assert_eq!(
+            CustomScale::from_name(note_c!(4), "whole-tone").map(spell),
+            Some(spell(WholeTone { root: note_c!(4) }))
+        );
+    }
+
+    #[test]
+    fn test_mode_rotation() {
+        let ionian = Ionian { root: note_c!(4) }.intervals();
+        let mut dorian = Dorian { root: note_d!(4) }.intervals();
+        dorian.sort_by_key(|a| a.size());
+        assert_eq!(mode(&ionian, 2), Some(dorian));
+
+        let names: Vec<_> = modes(&ionian).map(|(name, _)| name).collect();
+        assert_eq!(
+            names,
+            [
+                "ionian",
+                "dorian",
+                "phrygian",
+                "lydian",
+                "mixolydian",
+                "aeolian",
+                "locrian"
+            ]
+            .map(Some)
+        );
+
+        let names: Vec<_> = modes(&HarmonicMinor { root: note_a!(4) }.intervals())
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names[4], Some("phrygian dominant"));
+        assert_eq!(names[6], Some("super locrian double flat 7"));
+
+        let names: Vec<_> = modes(&MelodicMinor { root: note_c!(4) }.intervals())
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "melodic minor",
+                "dorian flat 2",
+                "lydian augmented",
+                "lydian dominant",
+                "mixolydian flat 6",
+                "locrian natural 2",
+                "altered"
+            ]
+            .map(Some)
+        );
+
+        let pentatonic = MajorPentatonic { root: note_c!(4) }.intervals();
+        assert_eq!(
+            modes(&pentatonic).nth(4).unwrap().0,
+            Some("minor pentatonic")
+        );
+
+        // Compound degrees are reduced before rotating.
+        let add9 = CustomScale::from_formula(note_c!(4), "1 3 5 9")
+            .unwrap()
+            .intervals;
+        assert_eq!(
+            mode(&add9, 1),
+            Some(vec![
+                Perfect(Unison),
+                Major(Second),
+                Major(Third),
+                Perfect(Fifth)
+            ])
+        );
+        assert_eq!(
+            mode(&add9, 4),
+            Some(vec![
+                Perfect(Unison),
+                Perfect(Fourth),
+                Perfect(Fifth),
+                Major(Sixth)
+            ])
+        );
+        assert_eq!(modes(&add9).count(), 4);
+        assert_eq!(mode(&add9, 0), None);
+        assert_eq!(mode(&[], 1), None);
+        assert_eq!(modes(&[]).count(), 0);
+
+        let diminished = HalfWholeDiminished { root: note_c!(4) }.intervals();
+        for (n, (_, mode)) in modes(&diminished).enumerate() {
+            let rotated = CustomScale {
+                root: note_c!(4),
+                intervals: mode,
+            };
+            assert_eq!(spell(&rotated).0.len(), 8);
+            if n % 2 == 0 {
+                let classes = |notes: Notes| -> HashSet<i32> {
+                    notes.into_iter().map(|n| n.pitch.pitch_class()).collect()
+                };
+                assert_eq!(
+                    classes(spell(rotated)),
+                    classes(spell(HalfWholeDiminished { root: note_c!(4) }))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_scale_degrees() {
+        let c_major = Major { root: note_c!(4) };
+        assert_eq!(c_major.degree(1), Some(note_c!(4)));
+        assert_eq!(c_major.degree(5), Some(note_g!(4)));
+        assert_eq!(c_major.degree(8), Some(note_c!(5)));
+        assert_eq!(c_major.degree(10), Some(note_e!(5)));
+        assert_eq!(c_major.degree(0), None);
+        let empty = CustomScale {
+            root: note_c!(4),
+            intervals: vec![],
+        };
+        assert_eq!(empty.degree(1), None);
+
+        assert_eq!(c_major.step(note_e!(4), 3), Some(note_a!(4)));
+        assert_eq!(c_major.step(note_b!(4), 2), Some(note_d!(5)));
+        assert_eq!(c_major.step(note_c!(4), -1), Some(octave(b!(), 3)));
+        assert_eq!(c_major.step(note_d!(2), -8), Some(octave(c!(), 1)));
+        assert_eq!(c_major.step(octave(bis!(), 3), 1), Some(note_d!(4)));
+        assert_eq!(c_major.step(octave(fis!(), 4), 1), None);
+
+        let melody = [note_c!(4), note_d!(4), note_e!(4), note_f!(4), note_g!(4)];
+        let e_flat_major = Major { root: note_ees!(4) };
+        let transposed: Vec<_> = melody
+            .iter()
+            .map(|&n| c_major.step(n, 2).unwrap())
+            .collect();
+        assert_eq!(
+            transposed,
+            vec![note_e!(4), note_f!(4), note_g!(4), note_a!(4), note_b!(4)]
+        );
+        assert_eq!(e_flat_major.step(note_g!(4), 2), Some(octave(bes!(), 4)));
+
+        let blues: Box<dyn Scale> = Box::new(Blues { root: note_a!(3) });
+        assert_eq!(blues.degree(4), Some(octave(dis!(), 4)));
+        assert_eq!(blues.step(note_e!(4), 2), Some(note_a!(4)));
+    }
+
+    #[test]
+    fn test_midi() {
+        assert_eq!(note_c!(4).to_midi(), Some(60));
+        assert_eq!(octave(bis!(), 3).to_midi(), Some(60));
+        assert_eq!(note_a!(4).to_midi(), Some(69));
+        assert_eq!(octave(ces!(), 0).to_midi(), Some(11));
+        assert_eq!(note_c!(11).to_midi(), None);
+
+        assert_eq!(
+            Note::from_midi(61, &Spelling::Sharps),
+            Some(octave(cis!(), 4))
+        );
+        assert_eq!(
+            Note::from_midi(61, &Spelling::Flats),
+            Some(octave(des!(), 4))
+        );
+        assert_eq!(Note::from_midi(11, &Spelling::Sharps), None);
+
+        let g_flat_major = pitches(&spell(Major {
+            root: octave(ges!(), 4),
+        }));
+        let key = Spelling::Key(g_flat_major);
+        assert_eq!(Note::from_midi(59, &key), Some(octave(ces!(), 4)));
+        assert_eq!(Note::from_midi(62, &key), Some(octave(d!(), 4)));
+        assert_eq!(Note::from_midi(57, &key), Some(octave(a!(), 3)));
+        assert_eq!(Note::from_midi(64, &key), Some(octave(e!(), 4)));
+
+        let c_sharp_major = Spelling::Key(pitches(&spell(Major {
+            root: octave(cis!(), 4),
+        })));
+        assert_eq!(Note::from_midi(60, &c_sharp_major), Some(octave(bis!(), 3)));
+        assert_eq!(Note::from_midi(62, &c_sharp_major), Some(octave(d!(), 4)));
+
+        for key in 13..=127 {
+            for spelling in [Spelling::Sharps, Spelling::Flats, c_sharp_major.clone()] {
+                assert_eq!(
+                    Note::from_midi(key, &spelling).unwrap().to_midi(),
+                    Some(key)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_frequency() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+
+        assert!(close(note_a!(4).frequency(), 440.0));
+        assert!(close(note_a!(5).frequency(), 880.0));
+        assert!(close(note_c!(4).frequency(), 261.625565));
+        assert!(close(octave(bis!(), 3).frequency(), note_c!(4).frequency()));
+        assert!(close(Tuning::BAROQUE.frequency(&note_a!(4)), 415.0));
+        assert!(close(Tuning::ORCHESTRAL.frequency(&note_a!(3)), 221.0));
+
+        let (note, cents) = Note::nearest(440.0).unwrap();
+        assert_eq!(note, note_a!(4));
+        assert!(close(cents, 0.0));
+
+        let (note, cents) = Note::nearest(445.0).unwrap();
+        assert_eq!(note, note_a!(4));
+        assert!((cents - 19.56).abs() < 0.01);
+
+        let (note, cents) = Tuning::BAROQUE.nearest(440.0, &Spelling::Flats).unwrap();
+        assert_eq!(note, octave(bes!(), 4));
+        assert!((cents - 1.27).abs() < 0.01);
+
+        let (note, cents) = Note::nearest(275.0).unwrap();
+        assert_eq!(note, octave(cis!(), 4));
+        assert!(cents < 0.0);
+
+        assert_eq!(Note::nearest(0.0), None);
+        assert_eq!(Note::nearest(1.0), None);
+    }
+
+    #[test]
+    fn test_parse_notes() {
+        let note = |s: &str| s.parse::<Note>().unwrap();
+        let pitch = |s: &str| s.parse::<Pitch>().unwrap();
+
+        assert_eq!(note("C#4"), octave(cis!(), 4));
+        assert_eq!(note("Db3"), octave(des!(), 3));
+        assert_eq!(note("C♯4"), octave(cis!(), 4));
+        assert_eq!(note("bb3"), octave(bes!(), 3));
+        assert_eq!(note("F𝄪5").pitch.accidentals, 2);
+        assert_eq!(note("cis'"), octave(cis!(), 4));
+        assert_eq!(note("bes,"), octave(bes!(), 2));
+        assert_eq!(note("g"), note_g!(3));
+        assert_eq!(note("as''"), octave(aes!(), 5));
+
+        let b_double_flat = Pitch {
+            name: NoteName::B,
+            accidentals: -2,
+        };
+        assert_eq!(note("Bbb2"), octave(b_double_flat, 2));
+        assert_eq!(pitch("Bbb"), b_double_flat);
+        assert_eq!(pitch("eeses").accidentals, -2);
+        assert_eq!(pitch("eses"), pitch("eeses"));
+        assert_eq!(pitch("ais"), ais!());
+        assert_eq!(pitch("es"), ees!());
+
+        for note in [octave(fis!(), 4), octave(b_double_flat, 2), note_ees!(5)] {
+            assert_eq!(note.to_string().parse::<Note>(), Ok(note));
+            let dutch = note.pitch.fmt_as_code().replace("!()", "");
+            assert_eq!(dutch.parse::<Pitch>(), Ok(note.pitch));
+        }
+
+        let error = |s: &str| {
+            let error = s.parse::<Note>().unwrap_err();
+            (error.kind, error.span)
+        };
+        assert_eq!(error(""), (ParseNoteErrorKind::Empty, 0..0));
+        assert_eq!(error("H4"), (ParseNoteErrorKind::InvalidNoteName, 0..1));
+        assert_eq!(error("C#"), (ParseNoteErrorKind::MissingOctave, 2..2));
+        assert_eq!(error("C#4x"), (ParseNoteErrorKind::UnexpectedSymbol, 2..4));
+        assert_eq!(error("c,,,,"), (ParseNoteErrorKind::InvalidOctave, 1..5));
+        assert_eq!(
+            "C#4".parse::<Pitch>().unwrap_err().kind,
+            ParseNoteErrorKind::UnexpectedSymbol
+        );
+    }
+
+    #[test]
+    fn test_pitch_ordering() {
+        assert_eq!(octave(bis!(), 3).cmp_pitch(&note_c!(4)), Ordering::Less);
+        assert_eq!(
+            octave(ces!(), 4).cmp_pitch(&octave(b!(), 3)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            octave(ces!(), 4).cmp_pitch(&octave(bes!(), 3)),
+            Ordering::Greater
+        );
+        assert_eq!(note_c!(4).cmp_pitch(&note_c!(4)), Ordering::Equal);
+        assert_eq!(octave(gis!(), 4).cmp_pitch(&note_ees!(5)), Ordering::Less);
+
+        let mut notes = Notes(vec![
+            note_c!(5),
+            octave(ces!(), 4),
+            note_e!(4),
+            octave(bis!(), 3),
+            note_c!(4),
+            octave(b!(), 3),
+        ]);
+        notes.sort_by_pitch();
+        assert_eq!(
+            notes.0,
+            vec![
+                octave(b!(), 3),
+                octave(ces!(), 4),
+                octave(bis!(), 3),
+                note_c!(4),
+                note_e!(4),
+                note_c!(5),
+            ]
+        );
+        assert_eq!(notes.lowest(), Some(octave(b!(), 3)));
+        assert_eq!(notes.highest(), Some(note_c!(5)));
+        assert!(notes.within(octave(b!(), 3), note_c!(5)));
+        assert!(!notes.within(note_c!(4), note_c!(5)));
+    }
+
+    #[test]
+    fn test_enharmonics() {
+        assert_eq!(
+            c!().enharmonics(),
+            vec![c!(), "D𝄫".parse().unwrap(), bis!()]
+        );
+        assert_eq!(gis!().enharmonics(), vec![gis!(), aes!()]);
+        assert!(c!()
+            .enharmonics()
+            .iter()
+            .all(|p| p.pitch_class() == c!().pitch_class()));
+
+        let simplify = |s: &str| s.parse::<Pitch>().unwrap().simplify();
+        assert_eq!(simplify("E𝄪"), fis!());
+        assert_eq!(simplify("C𝄫"), bes!());
+        assert_eq!(simplify("F𝄪"), g!());
+        assert_eq!(simplify("B♭"), bes!());
+        assert_eq!(simplify("E♯"), eis!());
+
+        assert_eq!(
+            "B♯3".parse::<Note>().unwrap().simplify(),
+            Some(octave(bis!(), 3))
+        );
+        assert_eq!(
+            "B𝄪3".parse::<Note>().unwrap().simplify(),
+            Some(octave(cis!(), 4))
+        );
+        assert_eq!("D𝄫4".parse::<Note>().unwrap().simplify(), Some(note_c!(4)));
+
+        let f_major = Spelling::from_scale(Major { root: note_f!(4) });
+        assert_eq!(ais!().respell(&f_major), bes!());
+        assert_eq!(dis!().respell(&f_major), ees!());
+        assert_eq!(ges!().respell(&Spelling::Sharps), fis!());
+        let e_major = Spelling::from_scale(Major { root: note_e!(4) });
+        assert_eq!(aes!().respell(&e_major), gis!());
+        assert_eq!(octave(ces!(), 4).respell(&e_major), Some(octave(b!(), 3)));
+    }
+
+    #[test]
+    fn test_key_signatures() {
+        assert_eq!(
+            KeySignature::from_scale(Major { root: note_d!(4) }),
+            KeySignature::Standard(2)
+        );
+        assert_eq!(
+            KeySignature::from_scale(Aeolian { root: note_b!(3) }),
+            KeySignature::Standard(2)
+        );
+        assert_eq!(
+            KeySignature::from_scale(Dorian { root: note_g!(4) }),
+            KeySignature::Standard(-1)
+        );
+        assert_eq!(
+            KeySignature::from_scale(Major {
+                root: octave(cis!(), 4)
+            }),
+            KeySignature::Standard(7)
+        );
+        assert_eq!(
+            KeySignature::from_scale(Major { root: note_ces!(4) }),
+            KeySignature::Standard(-7)
+        );
+        let harmonic = KeySignature::from_scale(HarmonicMinor { root: note_d!(4) });
+        assert_eq!(harmonic.fifths(), None);
+        assert_eq!(harmonic.accidental(NoteName::B), -1);
+        assert_eq!(harmonic.accidental(NoteName::C), 1);
+        assert_eq!(harmonic.to_string(), "C♯ B♭");
+
+        let e_flat = KeySignature::Standard(-3);
+        assert_eq!(e_flat.to_string(), "B♭ E♭ A♭");
+        assert_eq!(KeySignature::Standard(4).to_string(), "F♯ C♯ G♯ D♯");
+        assert_eq!(KeySignature::default().to_string(), "♮");
+        assert_eq!(e_flat.accidental(NoteName::A), -1);
+        assert_eq!(e_flat.accidental(NoteName::D), 0);
+        assert!(!e_flat.needs_accidental(&bes!()));
+        assert!(e_flat.needs_accidental(&b!()));
+        assert!(e_flat.needs_accidental(&des!()));
+
+        assert_eq!(
+            e_flat.printed_accidentals(&[
+                note_ees!(4),
+                note_e!(4),
+                note_e!(4),
+                note_e!(5),
+                note_ees!(4),
+                octave(aes!(), 4),
+            ]),
+            vec![None, Some(0), None, Some(0), Some(-1), None]
+        );
+
+        let spelling = Spelling::Signature(e_flat);
+        assert_eq!(Note::from_midi(68, &spelling), Some(octave(aes!(), 4)));
+        assert_eq!(Note::from_midi(66, &spelling), Some(octave(ges!(), 4)));
+        assert_eq!(
+            Note::from_midi(66, &Spelling::Signature(KeySignature::Standard(1))),
+            Some(octave(fis!(), 4))
+        );
+    }
+
+    #[test]
+    fn test_harmonize() {
+        let symbols = |chords: Vec<Option<Chord>>| -> Vec<String> {
+            chords.into_iter().map(|c| c.unwrap().to_string()).collect()
+        };
+
+        let c_major = Ionian { root: note_c!(4) };
+        assert_eq!(
+            symbols(chords::harmonize(&c_major, Extensions::Seventh)),
+            vec!["Cmaj7", "Dm7", "Em7", "Fmaj7", "G7", "Am7", "Bm7♭5"]
+        );
+        assert_eq!(
+            symbols(chords::harmonize(&c_major, Extensions::Triad)),
+            vec!["C", "Dm", "Em", "F", "G", "Am", "Bdim"]
+        );
+        assert_eq!(
+            symbols(chords::harmonize(&c_major, Extensions::Ninth)),
+            vec!["Cmaj9", "Dm9", "Em7♭9", "Fmaj9", "G9", "Am9", "Bm7♭5♭9"]
+        );
+        assert_eq!(
+            symbols(chords::harmonize(
+                HarmonicMinor { root: note_a!(4) },
+                Extensions::Seventh
+            )),
+            vec![
+                "Am(maj7)",
+                "Bm7♭5",
+                "Caug(maj7)",
+                "Dm7",
+                "E7",
+                "Fmaj7",
+                "G♯dim7"
+            ]
+        );
+        assert_eq!(
+            symbols(chords::harmonize(
+                Dorian { root: note_d!(4) },
+                Extensions::Seventh
+            )),
+            vec!["Dm7", "Em7", "Fmaj7", "G7", "Am7", "Bm7♭5", "Cmaj7"]
+        );
+
+        let chord =
+            chords::diatonic_chord(Major { root: note_e!(4) }, 5, Extensions::Seventh).unwrap();
+        assert_eq!(chord.to_string(), "B7");
+        assert_eq!(chord.root, note_b!(4));
+        assert_eq!(
+            chords::diatonic_chord(Major { root: note_e!(4) }, 0, Extensions::Triad),
+            None
+        );
+    }
+
+    #[test]
+    fn test_roman_numerals() {
+        let c_major = Major { root: note_c!(4) };
+        let analyze = |key: &dyn Scale, symbol: &str| {
+            RomanNumeral::analyze(key, &symbol.parse().unwrap()).map(|n| n.to_string())
+        };
+
+        assert_eq!(analyze(&c_major, "Bb").as_deref(), Some("♭VII"));
+        assert_eq!(analyze(&c_major, "G7/B").as_deref(), Some("V⁶₅"));
+        assert_eq!(analyze(&c_major, "G7/F").as_deref(), Some("V⁴₂"));
+        assert_eq!(analyze(&c_major, "Bdim7").as_deref(), Some("vii°7"));
+        assert_eq!(analyze(&c_major, "Bm7b5").as_deref(), Some("viiø7"));
+        assert_eq!(analyze(&c_major, "Dm/A").as_deref(), Some("ii⁶₄"));
+        assert_eq!(analyze(&c_major, "Fmaj7").as_deref(), Some("IVmaj7"));
+        assert_eq!(analyze(&c_major, "F#dim").as_deref(), Some("♯iv°"));
+        assert_eq!(analyze(&c_major, "Csus4"), None);
+        assert_eq!(analyze(&c_major, "C/D"), None);
+
+        let c_minor = Aeolian { root: note_c!(4) };
+        assert_eq!(analyze(&c_minor, "Bb").as_deref(), Some("VII"));
+        assert_eq!(analyze(&c_minor, "Eb+").as_deref(), Some("III+"));
+        assert_eq!(analyze(&c_minor, "G7").as_deref(), Some("V7"));
+
+        for symbol in [
+            "♭VII",
+            "V⁶₅",
+            "vii°7",
+            "iiø⁴₃",
+            "Imaj7",
+            "III+",
+            "ii⁶",
+            "IV⁶₄",
+        ] {
+            let numeral: RomanNumeral = symbol.parse().unwrap();
+            assert_eq!(numeral.to_string(), symbol);
+        }
+        assert_eq!("bVII".parse::<RomanNumeral>().unwrap().to_string(), "♭VII");
+        assert_eq!("V65".parse::<RomanNumeral>().unwrap().to_string(), "V⁶₅");
+        assert_eq!(
+            "viio7".parse::<RomanNumeral>().unwrap().to_string(),
+            "vii°7"
+        );
+
+        let numeral = RomanNumeral::new(5, 0, Qualities::Major, Some(Sevenths::Minor), 1).unwrap();
+        assert_eq!(numeral, "V65".parse().unwrap());
+        assert_eq!((numeral.degree(), numeral.inversion()), (5, 1));
+        assert_eq!(RomanNumeral::new(0, 0, Qualities::Major, None, 0), None);
+        assert_eq!(RomanNumeral::new(8, 0, Qualities::Major, None, 0), None);
+        assert_eq!(RomanNumeral::new(1, 0, Qualities::Major, None, 3), None);
+        assert_eq!(RomanNumeral::new(1, 0, Qualities::Sus4, None, 0), None);
+
+        let realize = |numeral: &str, key: &dyn Scale| {
+            numeral
+                .parse::<RomanNumeral>()
+                .unwrap()
+                .realize(key)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(realize("V65", &c_major), "G7/B");
+        assert_eq!(realize("bVII", &c_major), "B♭");
+        assert_eq!(realize("vii°7", &c_minor), "B♭dim7");
+        assert_eq!(realize("viiø7", &Major { root: note_e!(4) }), "D♯m7♭5");
+
+        let progression = roman::parse_progression("ii7–V7–Imaj7").unwrap();
+        for (key, expected) in [
+            (note_c!(4), ["Dm7", "G7", "Cmaj7"]),
+            (note_ees!(4), ["Fm7", "B♭7", "E♭maj7"]),
+            (note_b!(3), ["C♯m7", "F♯7", "Bmaj7"]),
+        ] {
+            let chords: Vec<String> = progression
+                .iter()
+                .map(|n| n.realize(Major { root: key }).unwrap().to_string())
+                .collect();
+            assert_eq!(chords, expected);
+        }
+        assert_eq!(
+            roman::parse_progression("I - vi - IV - V").unwrap().len(),
+            4
+        );
+
+        let error = roman::parse_progression("ii–X–I").unwrap_err();
+        assert_eq!(error.kind, ParseRomanNumeralErrorKind::InvalidNumeral);
+        assert_eq!(error.span, 5..6);
+        let error = "V9".parse::<RomanNumeral>().unwrap_err();
+        assert_eq!(error.kind, ParseRomanNumeralErrorKind::InvalidFigure);
+        assert_eq!(error.span, 1..2);
+    }
+
+    #[test]
+    fn test_voicings() {
+        let chord = |symbol: &str| symbol.parse::<Chord>().unwrap();
+        let voiced = |symbol: &str, voicing, inversion| {
+            voice(&chord(symbol), voicing, inversion, note_c!(3), note_c!(6))
+                .into_iter()
+                .map(|notes| notes.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            voiced("C7", Voicings::Close, 0),
+            vec!["C3, E3, G3, B♭3", "C4, E4, G4, B♭4", "C5, E5, G5, B♭5"]
+        );
+        assert_eq!(voiced("C7", Voicings::Close, 1)[0], "E3, G3, B♭3, C4");
+        assert_eq!(voiced("C", Voicings::Close, 2)[0], "G3, C4, E4");
+        assert_eq!(voiced("C", Voicings::Open, 0)[0], "C3, G3, E4");
+        assert_eq!(voiced("Cmaj7", Voicings::Drop2, 0)[0], "G3, C4, E4, B4");
+        assert_eq!(voiced("Cmaj7", Voicings::Drop3, 0)[0], "E3, C4, G4, B4");
+        assert_eq!(voiced("C7", Voicings::Shell, 0)[0], "C3, E3, B♭3");
+        assert_eq!(voiced("C7", Voicings::Shell, 1)[0], "C3, B♭3, E4");
+        assert_eq!(voiced("C6", Voicings::Shell, 0)[0], "C3, E3, A3");
+        assert_eq!(voiced("Dm7", Voicings::RootlessA, 0)[0], "F3, A3, C4, E4");
+        assert_eq!(voiced("Dm7", Voicings::RootlessB, 0)[0], "C3, E3, F3, A3");
+        assert_eq!(voiced("G13", Voicings::RootlessA, 0)[0], "B3, E4, F4, A4");
+        assert_eq!(voiced("G7", Voicings::RootlessB, 0)[0], "F3, A3, B3, D4");
+        assert_eq!(voiced("C/E", Voicings::Close, 0)[0], "E3, C4, E4, G4");
+        assert_eq!(voiced("C/E", Voicings::Close, 1)[0], "E3, G3, C4");
+
+        assert!(voiced("C", Voicings::Drop3, 0).is_empty());
+        assert!(voiced("Csus4", Voicings::Shell, 0).is_empty());
+        assert!(voice(&chord("C13"), Voicings::Close, 0, note_c!(4), note_e!(4)).is_empty());
+
+        for voicing in voice(&chord("Fmaj9"), Voicings::Drop2, 0, note_e!(2), note_g!(5)) {
+            assert!(voicing.within(note_e!(2), note_g!(5)));
+            assert_eq!(voicing.0.len(), 5);
+        }
+    }
+
+    #[test]
+    fn test_voice_leading() {
+        let chord = |symbol: &str| symbol.parse::<Chord>().unwrap();
+        let c_major = Notes(vec![note_c!(4), note_e!(4), note_g!(4)]);
+        let constraints = Constraints::default();
+
+        let f_major = voice_leading::lead(&c_major, &chord("F"), &constraints).unwrap();
+        assert_eq!(f_major.to_string(), "C4, F4, A4");
+        assert_eq!(voice_leading::movement(&c_major, &f_major), 3);
+
+        let g7 = voice_leading::lead(&c_major, &chord("G7"), &constraints).unwrap();
+        assert_eq!(g7.to_string(), "B3, F4, G4");
+
+        let c_over_e = voice_leading::lead(&c_major, &chord("C/E"), &constraints).unwrap();
+        assert_eq!(c_over_e.to_string(), "E4, G4, C5");
+
+        let start = Notes(vec![note_d!(4), note_f!(4), note_a!(4), note_c!(5)]);
+        let progression: Vec<String> =
+            voice_leading::lead_progression(&start, &[chord("G7"), chord("Cmaj7")], &constraints)
+                .unwrap()
+                .iter()
+                .map(|notes| notes.to_string())
+                .collect();
+        assert_eq!(progression, vec!["D4, F4, G4, B4", "C4, E4, G4, B4"]);
+
+        let mut constraints = Constraints {
+            ranges: vec![
+                (note_c!(3), note_c!(5)),
+                (note_c!(3), note_c!(5)),
+                (note_c!(3), note_g!(4)),
+            ],
+            ..Constraints::default()
+        };
+        let f_major = voice_leading::lead(&c_major, &chord("F"), &constraints).unwrap();
+        assert_eq!(f_major.to_string(), "A3, C4, F4");
+        constraints.common_tones = true;
+        assert_eq!(
+            voice_leading::lead(&c_major, &chord("F"), &constraints),
+            None
+        );
+        let keep = Constraints {
+            common_tones: true,
+            ..Constraints::default()
+        };
+        let a_minor = voice_leading::lead(&c_major, &chord("Am"), &keep).unwrap();
+        assert_eq!(a_minor.to_string(), "C4, E4, A4");
+        assert_eq!(
+            a_minor,
+            voice_leading::lead(&c_major, &chord("Am"), &Constraints::default()).unwrap()
+        );
+        constraints.common_tones = false;
+
+        constraints.crossing = true;
+        let crossed = voice_leading::lead(&c_major, &chord("F"), &constraints).unwrap();
+        assert_eq!(crossed.to_string(), "C4, A4, F4");
+        assert_eq!(voice_leading::movement(&c_major, &crossed), 7);
+        for (note, (low, high)) in crossed.0.iter().zip(&constraints.ranges) {
+            assert!(note.position() >= low.position() && note.position() <= high.position());
+        }
+
+        // A slash bass outside the chord leaves one voice fewer for the chord tones.
+        let wide = Notes(vec![note_c!(3), note_e!(4), note_g!(4)]);
+        let slash = voice_leading::lead(&wide, &chord("C/D"), &Constraints::default()).unwrap();
+        assert_eq!(slash.to_string(), "D3, E4, C5");
+        let slash = voice_leading::lead(&c_major, &chord("F/G"), &Constraints::default()).unwrap();
+        assert_eq!(slash.to_string(), "G3, F4, A4");
+    }
+
+    #[test]
+    fn test_midi_export() {
+        let mut track = Track::default();
+        track.push_melody(&Notes(vec![note_c!(4), note_d!(4)]), 96);
+        let file = MidiFile {
+            format: Format::Single,
+            division: 96,
+            key_signature: Some(KeySignature::from_scale(Major { root: note_g!(4) })),
+            tracks: vec![track],
+            ..MidiFile::default()
+        };
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 41,
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20,
+            0x00, 0xFF, 0x58, 0x04, 0x04, 0x02, 0x18, 0x08,
+            0x00, 0xFF, 0x59, 0x02, 0x01, 0x00,
+            0x00, 0x90, 60, 100,
+            0x60, 0x80, 60, 0,
+            0x00, 0x90, 62, 100,
+            0x60, 0x80, 62, 0,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        assert_eq!(file.to_bytes().unwrap(), expected);
+        let mut written = vec![];
+        file.write(&mut written).unwrap();
+        assert_eq!(written, expected);
+
+        let mut track = Track {
+            name: "Cm".to_string(),
+            channel: 1,
+            ..Track::default()
+        };
+        track.push_chord(&chords::spell(&"Cm".parse().unwrap()), 480);
+        track.push_chord(&Notes(vec![note_c!(4)]), 480);
+        let mut file = MidiFile {
+            key_signature: Some(KeySignature::Standard(-3)),
+            minor: true,
+            time_signature: (3, 4),
+            tracks: vec![track],
+            ..MidiFile::default()
+        };
+        assert!(file.set_bpm(90.0));
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 1, 0, 2, 0x01, 0xE0,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 25,
+            0x00, 0xFF, 0x51, 0x03, 0x0A, 0x2C, 0x2B,
+            0x00, 0xFF, 0x58, 0x04, 0x03, 0x02, 0x18, 0x08,
+            0x00, 0xFF, 0x59, 0x02, 0xFD, 0x01,
+            0x00, 0xFF, 0x2F, 0x00,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 44,
+            0x00, 0xFF, 0x03, 0x02, b'C', b'm',
+            0x00, 0x91, 60, 100,
+            0x00, 0x91, 63, 100,
+            0x00, 0x91, 67, 100,
+            0x83, 0x60, 0x81, 60, 0,
+            0x00, 0x81, 63, 0,
+            0x00, 0x81, 67, 0,
+            0x00, 0x91, 60, 100,
+            0x83, 0x60, 0x81, 60, 0,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        assert_eq!(file.to_bytes().unwrap(), expected);
+
+        file.time_signature = (3, 5);
+        assert_eq!(file.to_bytes(), None);
+        file.time_signature = (3, 4);
+        file.tracks[0].push_melody(&Notes(vec![note_c!(10)]), 1);
+        assert_eq!(file.to_bytes(), None);
+        assert!(file.write(&mut vec![]).is_err());
+
+        // Tempos beyond three bytes and ticks beyond the end of time cannot be written.
+        let mut file = MidiFile::default();
+        for bpm in [0.0, -120.0, 3.5, f64::NAN, f64::INFINITY] {
+            assert!(!file.set_bpm(bpm));
+        }
+        assert_eq!(file.tempo, 500_000);
+        assert!(file.set_bpm(4.0));
+        assert_eq!(file.tempo, 15_000_000);
+        assert!(file.to_bytes().is_some());
+        for tempo in [0, MidiFile::MAX_TEMPO + 1] {
+            file.tempo = tempo;
+            assert_eq!(file.to_bytes(), None);
+        }
+        file.tempo = 500_000;
+        let mut track = Track::default();
+        track.push_melody(&Notes(vec![note_c!(4)]), 480);
+        track.events[0].start = u32::MAX - 100;
+        assert_eq!(track.end(), u32::MAX);
+        file.tracks = vec![track];
+        assert_eq!(file.to_bytes(), None);
+    }
+
+    #[test]
+    fn test_midi_import() {
+        let mut track = Track {
+            name: "Cm".to_string(),
+            channel: 1,
+            ..Track::default()
+        };
+        track.push_chord(&chords::spell(&"Cm".parse().unwrap()), 480);
+        track.push_melody(&spell(Aeolian { root: note_c!(4) }), 240);
+        let mut file = MidiFile {
+            key_signature: Some(KeySignature::Standard(-3)),
+            minor: true,
+            time_signature: (3, 4),
+            tracks: vec![track],
+            ..MidiFile::default()
+        };
+        assert!(file.set_bpm(90.0));
+        let read = MidiFile::from_bytes(&file.to_bytes().unwrap()).unwrap();
+        assert_eq!(read, file);
+
+        file.format = Format::Single;
+        let read = MidiFile::from_bytes(&file.to_bytes().unwrap()).unwrap();
+        assert_eq!(read, file);
+
+        // Running status, a note on with velocity 0 as note off and a key change.
+        #[rustfmt::skip]
+        let bytes: Vec<u8> = vec![
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 31,
+            0x00, 0xFF, 0x59, 0x02, 0x02, 0x00,
+            0x00, 0x90, 66, 100,
+            0x60, 66, 0,
+            0x00, 0xFF, 0x59, 0x02, 0xFE, 0x00,
+            0x00, 0x90, 70, 100,
+            0x60, 0x80, 70, 64,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        let read = MidiFile::from_bytes(&bytes).unwrap();
+        assert_eq!(read.format, Format::Single);
+        assert_eq!(read.key_signature, Some(KeySignature::Standard(2)));
+        assert_eq!(read.tempo, 500_000);
+        let notes: Vec<(Note, u32, u32)> = read.tracks[0]
+            .events
+            .iter()
+            .map(|e| (e.note, e.start, e.duration))
+            .collect();
+        assert_eq!(
+            notes,
+            vec![(octave(fis!(), 4), 0, 96), (octave(bes!(), 4), 96, 96)]
+        );
+
+        // Without a key signature the key is detected from the notes.
+        let mut track = Track::default();
+        track.push_melody(&spell(Major { root: note_aes!(4) }), 96);
+        let file = MidiFile {
+            tracks: vec![track.clone()],
+            ..MidiFile::default()
+        };
+        let read = MidiFile::from_bytes(&file.to_bytes().unwrap()).unwrap();
+        assert_eq!(read.key_signature, None);
+        assert_eq!(read.tracks, vec![track]);
+        assert_eq!(
+            KeySignature::detect([1, 3, 5, 6, 8, 10, 0]),
+            KeySignature::Standard(-5)
+        );
+        assert_eq!(KeySignature::detect([0, 4, 7]), KeySignature::Standard(0));
+
+        let error = MidiFile::from_bytes(b"RIFF").unwrap_err();
+        assert_eq!(error.kind, ParseMidiErrorKind::InvalidHeader);
+        let error = MidiFile::from_bytes(&bytes[..30]).unwrap_err();
+        assert_eq!(error.kind, ParseMidiErrorKind::UnexpectedEnd);
+        let mut invalid = bytes.clone();
+        invalid[29] = 0xF4;
+        let error = MidiFile::from_bytes(&invalid).unwrap_err();
+        assert_eq!(error.kind, ParseMidiErrorKind::InvalidEvent);
+        assert_eq!(error.span, 29..30);
+
+        let chunk = |events: &[u8]| {
+            let mut bytes = b"MThd\0\0\0\x06\0\0\0\x01\x01\xE0MTrk".to_vec();
+            bytes.extend((events.len() as u32 + 4).to_be_bytes());
+            bytes.extend(events);
+            bytes.extend([0x00, 0xFF, 0x2F, 0x00]);
+            bytes
+        };
+        // Delta times that add up beyond `u32::MAX`.
+        let error = MidiFile::from_bytes(&chunk(
+            &[0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00].repeat(17),
+        ))
+        .unwrap_err();
+        assert_eq!(error.kind, ParseMidiErrorKind::InvalidEvent);
+        assert_eq!(error.span, 134..138);
+        // Keys below C0.
+        let error =
+            MidiFile::from_bytes(&chunk(&[0x00, 0x90, 5, 100, 0x60, 0x80, 5, 0])).unwrap_err();
+        assert_eq!(error.kind, ParseMidiErrorKind::UnsupportedKey);
+    }
+
+    #[test]
+    fn test_lilypond() {
+        let mut score = Score {
+            key: Some((d!(), Mode::Major)),
+            time: Some((4, 4)),
+            ..Score::default()
+        };
+        score.push_notes(&spell(Major { root: note_d!(4) }), Duration::QUARTER);
+        score.push_notes(&Notes(vec![note_d!(3)]), Duration::HALF.dotted());
+        score.music.push((Element::Rest, Duration::QUARTER));
+        assert_eq!(
+            score.to_string(),
+            "\\version \"2.24.0\"\n\
+             \n\
+             \\relative c' {\n  \
+               \\clef treble\n  \
+               \\key d \\major\n  \
+               \\time 4/4\n  \
+               d4 e4 fis4 g4 a4 b4 cis4 d,,2. r4\n\
+             }\n"
+        );
+
+        score.octaves = Octaves::Absolute;
+        assert!(score
+            .to_string()
+            .contains("  d'4 e'4 fis'4 g'4 a'4 b'4 cis''4 d2. r4\n"));
+
+        let chords: Vec<Chord> = ["C", "F/A", "G7"]
+            .iter()
+            .map(|c| c.parse().unwrap())
+            .collect();
+        let mut score = Score {
+            clef: Clef::Bass,
+            key: Some((bes!(), Mode::Minor)),
+            ..Score::default()
+        };
+        score.push_chords(&chords, Duration::HALF);
+        score.push_notes(&Notes(vec![note_c!(4)]), Duration::WHOLE);
+        let document = score.to_string();
+        assert!(document.contains("  \\clef bass\n  \\key bes \\minor\n"));
+        assert!(document.contains("  <c e g>2 <a f' a c>2 <g' b d f>2 c,1\n"));
+        assert!(!document.contains("\\time"));
+        score.octaves = Octaves::Absolute;
+        assert!(score
+            .to_string()
+            .contains("  <c' e' g'>2 <a f' a' c''>2 <g' b' d'' f''>2 c'1\n"));
+        let ceseses = Note {
+            pitch: Pitch {
+                name: NoteName::C,
+                accidentals: -3,
+            },
+            octave: 4,
+        };
+        let mut score = Score::default();
+        score.push_notes(&Notes(vec![ceseses]), Duration::QUARTER);
+        assert!(score.to_string().contains("  a4\n"));
+
+        assert_eq!(Duration::QUARTER.dotted().ticks(480), Some(720));
+        assert_eq!(Duration::EIGHTH.dotted().dotted().ticks(480), Some(420));
+        assert_eq!(Duration { value: 0, dots: 0 }.ticks(480), None);
+        assert_eq!(Duration::WHOLE.ticks(u32::MAX), None);
+        assert_eq!(Duration { value: 1, dots: 40 }.ticks(480), None);
+        assert_eq!(Duration::from_ticks(480, u32::MAX), None);
+        assert_eq!(
+            Duration::from_ticks(720, 480),
+            Some(Duration::QUARTER.dotted())
+        );
+        assert_eq!(Duration::from_ticks(1920, 480), Some(Duration::WHOLE));
+        assert_eq!(Duration::from_ticks(100, 480), None);
+        assert_eq!(Duration::HALF.dotted().to_string(), "2.");
+        assert_eq!(cis!().dutch_name(), "cis");
+        assert_eq!(octave(ees!(), 4).fmt_as_code(), "ees!(4)");
+    }
+
+    #[test]
+    fn test_musicxml() {
+        let mut melody = Part {
+            name: "Melody & Bass".to_string(),
+            ..Part::default()
+        };
+        let cisis = Note {
+            pitch: Pitch {
+                name: NoteName::C,
+                accidentals: 2,
+            },
+            octave: 5,
+        };
+        melody.push_notes(
+            &Notes(vec![
+                note_d!(4),
+                octave(fis!(), 4),
+                octave(fis!(), 4),
+                cisis,
+            ]),
+            Duration::QUARTER,
+        );
+        melody.push_notes(&Notes(vec![octave(bes!(), 4)]), Duration::HALF.dotted());
+        melody.push_notes(&Notes(vec![note_b!(4)]), Duration::WHOLE);
+        let score = musicxml::Score {
+            key: Some(KeySignature::Standard(2)),
+            parts: vec![melody],
+            ..musicxml::Score::default()
+        };
+        let document = score.to_xml().unwrap();
+        assert!(document.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n\
+             <!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \
+             \"http://www.musicxml.org/dtds/partwise.dtd\">\n\
+             <score-partwise version=\"4.0\">\n  \
+               <part-list>\n    \
+                 <score-part id=\"P1\">\n      \
+                   <part-name>Melody &amp; Bass</part-name>\n"
+        ));
+        assert!(document.contains(
+            "        <divisions>480</divisions>\n        \
+             <key><fifths>2</fifths><mode>major</mode></key>\n        \
+             <time><beats>4</beats><beat-type>4</beat-type></time>\n        \
+             <clef><sign>G</sign><line>2</line></clef>\n"
+        ));
+        assert!(document.contains(
+            "        <pitch><step>C</step><alter>2</alter><octave>5</octave></pitch>\n        \
+             <duration>480</duration>\n        \
+             <voice>1</voice>\n        \
+             <type>quarter</type>\n        \
+             <accidental>double-sharp</accidental>\n"
+        ));
+        // Only the key signature marks F♯.
+        assert_eq!(
+            document.matches("<accidental>sharp</accidental>").count(),
+            0
+        );
+        assert_eq!(document.matches("<accidental>flat</accidental>").count(), 1);
+        assert_eq!(
+            document.matches("<accidental>natural</accidental>").count(),
+            1
+        );
+        // The whole note crosses into the third measure and is tied over the barline.
+        assert_eq!(document.matches("<measure number=").count(), 3);
+        assert!(document.contains(
+            "        <pitch><step>B</step><octave>4</octave></pitch>\n        \
+             <duration>480</duration>\n        \
+             <tie type=\"start\"/>\n"
+        ));
+        assert!(document.contains(
+            "        <duration>960</duration>\n        \
+             <tie type=\"stop\"/>\n        \
+             <tie type=\"start\"/>\n        \
+             <voice>1</voice>\n        \
+             <type>half</type>\n        \
+             <notations><tied type=\"stop\"/><tied type=\"start\"/></notations>\n"
+        ));
+
+        let chords: Vec<Chord> = ["Dm7b5", "G7b9", "Cmaj7/E", "C5"]
+            .iter()
+            .map(|c| c.parse().unwrap())
+            .collect();
+        let mut chart = Part::default();
+        chart.push_chords(&chords, Duration::HALF);
+        let score = musicxml::Score {
+            parts: vec![chart],
+            ..musicxml::Score::default()
+        };
+        let document = score.to_xml().unwrap();
+        assert!(document.contains(
+            "      <harmony>\n        \
+               <root><root-step>D</root-step></root>\n        \
+               <kind text=\"m7♭5\">half-diminished</kind>\n      \
+             </harmony>\n"
+        ));
+        assert!(document.contains(
+            "        <kind text=\"7♭9\">dominant</kind>\n        \
+             <degree><degree-value>9</degree-value><degree-alter>-1</degree-alter>\
+             <degree-type>add</degree-type></degree>\n"
+        ));
+        assert!(document.contains(
+            "        <kind text=\"maj7\">major-seventh</kind>\n        \
+             <bass><bass-step>E</bass-step></bass>\n"
+        ));
+        assert!(document.contains(">power</kind>\n"));
+        assert_eq!(document.matches("<chord/>").count(), 3 + 4 + 4 + 1);
+        assert_eq!(document.matches("<measure number=").count(), 2);
+
+        // Measures without a length cannot be written.
+        for (time, divisions) in [((0, 4), 480), ((3, 5), 480), ((1, 128), 1)] {
+            let score = musicxml::Score {
+                time,
+                divisions,
+                ..score.clone()
+            };
+            assert_eq!(score.to_xml(), None);
+        }
+        let score = musicxml::Score {
+            divisions: 1,
+            time: (1, 4),
+            parts: vec![Part {
+                music: vec![(Element::Rest, Duration::EIGHTH)],
+                ..Part::default()
+            }],
+            ..musicxml::Score::default()
+        };
+        assert_eq!(score.to_xml(), None);
+    }
+
+    #[test]
+    fn test_musicxml_import() {
+        let mut melody = Part {
+            name: "Flute".to_string(),
+            ..Part::default()
+        };
+        melody.push_notes(
+            &Notes(vec![octave(fis!(), 4), octave(ges!(), 4), note_e!(4)]),
+            Duration::QUARTER,
+        );
+        melody.push_notes(&Notes(vec![octave(bes!(), 4)]), Duration::HALF.dotted());
+        melody.music.push((Element::Rest, Duration::QUARTER));
+        melody.push_chords(&["Cmaj7".parse().unwrap()], Duration::WHOLE);
+        let score = musicxml::Score {
+            key: Some(KeySignature::Standard(-3)),
+            minor: true,
+            time: (3, 4),
+            parts: vec![melody],
+            ..musicxml::Score::default()
+        };
+        // Notes split over barlines are joined again, and spellings survive the round trip.
+        let read: musicxml::Score = score.to_xml().unwrap().parse().unwrap();
+        assert_eq!(read.key, score.key);
+        assert!(read.minor);
+        assert_eq!(read.time, (3, 4));
+        assert_eq!(read.parts[0].name, "Flute");
+        assert_eq!(read.parts[0].music, score.parts[0].music);
+        assert!(read.parts[0].harmony.is_empty());
+
+        let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!DOCTYPE score-partwise PUBLIC "-//Recordare//DTD MusicXML 4.0 Partwise//EN"
+              "http://www.musicxml.org/dtds/partwise.dtd">
+            <score-partwise version="4.0">
+              <part-list>
+                <score-part id="P1"><part-name>Piano &amp; Voice</part-name></score-part>
+              </part-list>
+              <part id="P1">
+                <!-- Second voice and grace notes are left out. -->
+                <measure number="1">
+                  <attributes>
+                    <divisions>2</divisions>
+                    <key><key-step>F</key-step><key-alter>1</key-alter>
+                      <key-step>B</key-step><key-alter>-1</key-alter></key>
+                  </attributes>
+                  <note><grace/><pitch><step>D</step><octave>5</octave></pitch>
+                    <voice>1</voice><type>eighth</type></note>
+                  <note><pitch><step>E</step><alter>-2</alter><octave>5</octave></pitch>
+                    <duration>4</duration><voice>1</voice><type>half</type></note>
+                  <note><pitch><step>B</step><alter>1</alter><octave>4</octave></pitch>
+                    <duration>3</duration><voice>1</voice><type>quarter</type><dot/></note>
+                  <note><chord/><pitch><step>D</step><alter>2</alter><octave>5</octave></pitch>
+                    <duration>3</duration><voice>1</voice><type>quarter</type><dot/></note>
+                  <note><rest measure="yes"/><duration>1</duration><voice>1</voice></note>
+                  <backup><duration>8</duration></backup>
+                  <note><pitch><step>C</step><octave>3</octave></pitch>
+                    <duration>8</duration><voice>2</voice><type>whole</type></note>
+                </measure>
+              </part>
+            </score-partwise>
+        "#;
+        let read: musicxml::Score = document.parse().unwrap();
+        let eeses = Note {
+            pitch: Pitch {
+                name: NoteName::E,
+                accidentals: -2,
+            },
+            octave: 5,
+        };
+        let disis = Note {
+            pitch: Pitch {
+                name: NoteName::D,
+                accidentals: 2,
+            },
+            octave: 5,
+        };
+        assert_eq!(read.parts[0].name, "Piano & Voice");
+        assert_eq!(
+            read.parts[0].music,
+            vec![
+                (Element::Note(eeses), Duration::HALF),
+                (
+                    Element::Chord(Notes(vec![octave(bis!(), 4), disis])),
+                    Duration::QUARTER.dotted()
+                ),
+                (Element::Rest, Duration::EIGHTH),
+            ]
+        );
+        assert_eq!(
+            read.key,
+            Some(KeySignature::Custom(
+                [(NoteName::F, 1), (NoteName::B, -1)].into_iter().collect()
+            ))
+        );
+        // The interval between the written notes keeps its spelling.
+        assert_eq!(
+            octave(bis!(), 4).interval_to(&disis),
+            Some(Major(Third).into())
+        );
+
+        let error = "<score-timewise/>".parse::<musicxml::Score>().unwrap_err();
+        assert_eq!(error.kind, ParseMusicXmlErrorKind::UnsupportedDocument);
+        assert_eq!(error.span, 0..17);
+        let error = "<score-partwise><part></score-partwise>"
+            .parse::<musicxml::Score>()
+            .unwrap_err();
+        assert_eq!(error.kind, ParseMusicXmlErrorKind::InvalidXml);
+        assert_eq!(error.to_string(), "invalid XML at 22..38");
+        let error = "<a>"
+            .repeat(200_000)
+            .parse::<musicxml::Score>()
+            .unwrap_err();
+        assert_eq!(error.kind, ParseMusicXmlErrorKind::InvalidXml);
+        assert_eq!(error.span, 195..196);
+        let error = "<score-partwise><part><measure><note><pitch><step>H</step>\
+                     </pitch></note></measure></part></score-partwise>"
+            .parse::<musicxml::Score>()
+            .unwrap_err();
+        assert_eq!(error.kind, ParseMusicXmlErrorKind::InvalidValue);
+        assert_eq!(error.span, 50..51);
+
+        for (time, span) in [
+            ("0</beats><beat-type>4", 56..57),
+            ("3</beats><beat-type>6", 76..77),
+        ] {
+            let error = format!(
+                "<score-partwise><part><measure><attributes><time><beats>{}</beat-type>\
+                 </time></attributes></measure></part></score-partwise>",
+                time
+            )
+            .parse::<musicxml::Score>()
+            .unwrap_err();
+            assert_eq!(error.kind, ParseMusicXmlErrorKind::InvalidValue);
+            assert_eq!(error.span, span);
+        }
+
+        let error = "<score-partwise><part><measure><attributes>\
+                     <divisions>2147483647</divisions></attributes><note><pitch><step>C</step>\
+                     <octave>4</octave></pitch><duration>1</duration><type>eighth</type></note>\
+                     </measure></part></score-partwise>"
+            .parse::<musicxml::Score>()
+            .unwrap_err();
+        assert_eq!(error.kind, ParseMusicXmlErrorKind::InvalidValue);
+        assert_eq!(error.span, 54..64);
+
+        // Tuplets last no note value, whether or not their time modification is written.
+        let triplet = |modification: &str| {
+            format!(
+                "<score-partwise><part><measure><attributes><divisions>3</divisions>\
+                 </attributes><note><pitch><step>C</step><octave>4</octave></pitch>\
+                 <duration>1</duration><type>eighth</type>{}</note></measure></part>\
+                 </score-partwise>",
+                modification
+            )
+        };
+        let error = triplet(
+            "<time-modification><actual-notes>3</actual-notes>\
+                             <normal-notes>2</normal-notes></time-modification>",
+        )
+        .parse::<musicxml::Score>()
+        .unwrap_err();
+        assert_eq!(error.kind, ParseMusicXmlErrorKind::UnsupportedDuration);
+        assert_eq!(error.span, 174..193);
+        let error = triplet("").parse::<musicxml::Score>().unwrap_err();
+        assert_eq!(error.kind, ParseMusicXmlErrorKind::UnsupportedDuration);
+        assert_eq!(error.span, 143..144);
+    }
 }